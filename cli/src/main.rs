@@ -1,90 +1,19 @@
-use std::time::Duration;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use baz_core::{Board, Color, GamePlayer, Move, Position};
+use baz_core::{Board, Color, GamePlayer, Move, Winner, STARTING_FEN};
 use baz_dueler::StdioGamePlayer;
 use baz_players::{
-    ForwardRandomPlayer, GeniusHeuristic, GoFastHeuristic, GoFasterHeuristic, HResult,
-    HeuristicPlayer, MinMaxPlayer, NaiveHeuristic, RandomPlayer,
+    Budget, ForwardRandomPlayer, ForwardRandomPlayout, GeniusHeuristic, GoFastHeuristic,
+    GoFasterHeuristic, HResult, HeuristicPlayer, HeuristicPlayout, MctsPlayer, MinMaxPlayer,
+    NaiveHeuristic, PlayoutPolicy, RandomPlayer, RandomPlayout, Tablebase,
 };
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use num::Rational32;
 
-struct StdinHumanPlayer();
-
-impl GamePlayer for StdinHumanPlayer {
-    fn decide(&mut self, board: &Board, color: &Color) -> Move {
-        println!("{board:?}");
-        println!("{:?}'s turn", color);
-        loop {
-            let first_piece_index = StdinHumanPlayer::pick_piece(board);
-            if &board.pieces[first_piece_index].color != color {
-                // Selected one of their pieces, can't move it
-                println!("Choose one of your pieces");
-                continue;
-            }
-            let second_position_target = StdinHumanPlayer::pick_position();
-            let mov = if let HumanMoveTarget::Position(second_position) = second_position_target {
-                if let Some(second_piece_index) = board.get_piece_at(&second_position) {
-                    Move::Boom(second_piece_index)
-                } else {
-                    // Selected one of our pieces, zoom it
-                    Move::Zoom(first_piece_index, second_position)
-                }
-            } else {
-                Move::Score(first_piece_index)
-            };
-            if board
-                .legal_moves_for(&board.pieces[first_piece_index])
-                .any(|m| m == mov)
-            {
-                return mov;
-            } else {
-                println!("Invalid move");
-            }
-        }
-    }
-}
-
-enum HumanMoveTarget {
-    Position(Position),
-    ScoreZone,
-}
-
-impl StdinHumanPlayer {
-    fn pick_position() -> HumanMoveTarget {
-        let stdin = std::io::stdin();
-        let mut buffer = String::new();
-        let mut position: Result<Position, ()> = Err(());
-        while position.is_err() {
-            stdin
-                .read_line(&mut buffer)
-                .map_err(|_| ())
-                .expect("no IO error please");
-            let trim_buffer = buffer.trim();
-            if trim_buffer == "s" {
-                return HumanMoveTarget::ScoreZone;
-            }
-            position = trim_buffer.try_into();
-            if position.is_err() {
-                println!("Not a valid square");
-            }
-        }
-        HumanMoveTarget::Position(position.unwrap())
-    }
-    fn pick_piece(board: &Board) -> usize {
-        let mut piece_index = None;
-        while piece_index.is_none() {
-            if let HumanMoveTarget::Position(position) = Self::pick_position() {
-                piece_index = board.get_piece_at(&position);
-            }
-            if piece_index.is_none() {
-                println!("No piece there")
-            }
-        }
-        piece_index.unwrap()
-    }
-}
-
 #[derive(Parser, Debug)]
 #[command(author, version)]
 struct Args {
@@ -95,9 +24,30 @@ struct Args {
 #[derive(Debug, Subcommand)]
 enum Commands {
     Play {
+        // How long the player should take to decide each move, in milliseconds
+        #[arg(long)]
+        time_ms: Option<u64>,
+        // An endgame tablebase for the minmax players, as written by the tablebase command
+        #[arg(long)]
+        tablebase: Option<PathBuf>,
         #[command(subcommand)]
         player: PlayerOptions,
     },
+    // Count the positions reachable in exactly depth moves
+    Perft {
+        depth: usize,
+        #[arg(long, default_value = STARTING_FEN)]
+        fen: String,
+        // Also print the count below every move
+        #[arg(long)]
+        divide: bool,
+    },
+    // Solve every endgame with up to this many pieces left, and write the results to output. 3
+    // pieces takes a few minutes, and anything more is out of reach.
+    Tablebase {
+        pieces: usize,
+        output: PathBuf,
+    },
     // Not very interesting
     // Duel,
     // I'm lazy, godot already exists
@@ -112,6 +62,19 @@ enum PlayerOptions {
     GoFaster,
     Genius,
     Naive,
+    // Monte Carlo tree search, which doesn't need a heuristic unless the playouts use one
+    Mcts {
+        #[arg(long, value_enum, default_value_t = PlayoutOptions::ForwardRandom)]
+        playout: PlayoutOptions,
+    },
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum PlayoutOptions {
+    Random,
+    ForwardRandom,
+    // Guided by the go faster heuristic
+    Heuristic,
 }
 
 enum AIPlayer {
@@ -121,6 +84,7 @@ enum AIPlayer {
     GoFaster(HeuristicPlayer<GoFasterHeuristic, i8>),
     Genius(MinMaxPlayer<GeniusHeuristic, HResult<Rational32>>),
     Naive(MinMaxPlayer<NaiveHeuristic, HResult<Rational32>>),
+    Mcts(MctsPlayer<Box<dyn PlayoutPolicy + Send + Sync>>),
 }
 impl From<PlayerOptions> for AIPlayer {
     fn from(value: PlayerOptions) -> Self {
@@ -139,31 +103,130 @@ impl From<PlayerOptions> for AIPlayer {
                 NaiveHeuristic(),
                 Duration::from_millis(10),
             )),
+            PlayerOptions::Mcts { playout } => {
+                let policy: Box<dyn PlayoutPolicy + Send + Sync> = match playout {
+                    PlayoutOptions::Random => Box::new(RandomPlayout()),
+                    PlayoutOptions::ForwardRandom => Box::new(ForwardRandomPlayout()),
+                    PlayoutOptions::Heuristic => {
+                        Box::new(HeuristicPlayout::new(GoFasterHeuristic()))
+                    }
+                };
+                AIPlayer::Mcts(MctsPlayer::new(
+                    policy,
+                    Budget::Time(Duration::from_millis(10)),
+                ))
+            }
         }
     }
 }
-impl GamePlayer for AIPlayer {
-    fn decide(&mut self, board: &Board, color: &Color) -> Move {
+impl AIPlayer {
+    // Only the minmax players can use a tablebase, the rest are left alone
+    fn with_tablebase(self, tablebase: Arc<Tablebase>) -> AIPlayer {
+        match self {
+            AIPlayer::Genius(player) => AIPlayer::Genius(player.with_tablebase(tablebase)),
+            AIPlayer::Naive(player) => AIPlayer::Naive(player.with_tablebase(tablebase)),
+            player => player,
+        }
+    }
+    fn player(&mut self) -> &mut dyn GamePlayer {
         match self {
-            AIPlayer::Random(player) => player.decide(board, color),
-            AIPlayer::RandomForward(player) => player.decide(board, color),
-            AIPlayer::GoFast(player) => player.decide(board, color),
-            AIPlayer::GoFaster(player) => player.decide(board, color),
-            AIPlayer::Genius(player) => player.decide(board, color),
-            AIPlayer::Naive(player) => player.decide(board, color),
+            AIPlayer::Random(player) => player,
+            AIPlayer::RandomForward(player) => player,
+            AIPlayer::GoFast(player) => player,
+            AIPlayer::GoFaster(player) => player,
+            AIPlayer::Genius(player) => player,
+            AIPlayer::Naive(player) => player,
+            AIPlayer::Mcts(player) => player,
         }
     }
 }
+impl GamePlayer for AIPlayer {
+    fn decide(&mut self, board: &Board, color: &Color) -> Move {
+        self.player().decide(board, color)
+    }
+    fn new_game(&mut self, board: &Board, color: &Color) {
+        self.player().new_game(board, color)
+    }
+    fn opponent_moved(&mut self, mov: &Move, board: &Board) {
+        self.player().opponent_moved(mov, board)
+    }
+    fn game_over(&mut self, winner: &Winner) {
+        self.player().game_over(winner)
+    }
+    fn set_time_budget(&mut self, budget: Duration) {
+        self.player().set_time_budget(budget)
+    }
+}
 
 fn main() -> std::io::Result<()> {
     // let mut game = Game::new(RandomPlayer(), StdinHumanPlayer());
     let args = Args::parse();
     match args.command {
-        Commands::Play { player } => {
-            let ai = AIPlayer::from(player);
+        Commands::Play {
+            time_ms,
+            tablebase,
+            player,
+        } => {
+            let mut ai = AIPlayer::from(player);
+            if let Some(path) = tablebase {
+                match Tablebase::read_from(BufReader::new(File::open(path)?)) {
+                    Ok(tablebase) => ai = ai.with_tablebase(Arc::new(tablebase)),
+                    Err(err) => {
+                        eprintln!("Invalid tablebase: {err}");
+                        std::process::exit(1);
+                    }
+                }
+            }
             let mut stdio_player = StdioGamePlayer::new(ai);
+            if let Some(time_ms) = time_ms {
+                stdio_player = stdio_player.with_time_budget(Duration::from_millis(time_ms));
+            }
             stdio_player.main()?;
         }
+        Commands::Perft { depth, fen, divide } => {
+            let (board, turn) = match Board::from_fen(&fen) {
+                Ok(position) => position,
+                Err(err) => {
+                    eprintln!("Invalid FEN: {err}");
+                    std::process::exit(1);
+                }
+            };
+            let start = Instant::now();
+            let nodes = if divide {
+                let divide = board.perft_divide(&turn, depth);
+                for (mov, count) in divide.iter() {
+                    println!("{}: {count}", mov.to_notation(&board));
+                }
+                println!();
+                divide.iter().map(|(_, count)| count).sum()
+            } else {
+                board.perft(&turn, depth)
+            };
+            let elapsed = start.elapsed();
+            println!("Nodes: {nodes}");
+            println!(
+                "Time: {elapsed:?} ({:.0} nodes/s)",
+                nodes as f64 / elapsed.as_secs_f64()
+            );
+        }
+        Commands::Tablebase { pieces, output } => {
+            let start = Instant::now();
+            let tablebase = match Tablebase::generate(pieces) {
+                Ok(tablebase) => tablebase,
+                Err(err) => {
+                    eprintln!("Can't generate a tablebase: {err}");
+                    std::process::exit(1);
+                }
+            };
+            println!(
+                "Solved {} positions in {:?}",
+                tablebase.len(),
+                start.elapsed()
+            );
+            let mut writer = BufWriter::new(File::create(output)?);
+            tablebase.write_to(&mut writer)?;
+            writer.flush()?;
+        }
     }
     // let mut game = Game::new(RandomPlayer(), RandomPlayer());
     // game.finish_game();
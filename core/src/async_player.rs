@@ -0,0 +1,233 @@
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::task::Poll;
+use std::thread;
+use std::time::Duration;
+
+use crate::{Board, Color, GamePlayer, Move, Winner};
+
+/*
+ * Players that don't block while they decide, so front ends can stay responsive.
+ *
+ * An AsyncGamePlayer is asked to start deciding, and then polled until its move is ready:
+ *
+ *   player.start(&board, &color);
+ *   // Every frame:
+ *   if let Poll::Ready(mov) = player.poll() { ... }
+ *
+ * ThreadedPlayer runs any GamePlayer on a worker thread to make it an AsyncGamePlayer. It is also a
+ * GamePlayer itself, which blocks in decide, and passes the lifecycle methods on to the worker.
+ *
+ * GamePlayer::decide can't be interrupted, so cancelling only throws the result away. The worker
+ * finishes the cancelled decision before starting on the next one.
+ */
+
+pub trait AsyncGamePlayer {
+    // Starts deciding on a move. Any decision already in progress is cancelled.
+    fn start(&mut self, board: &Board, color: &Color);
+    // Ready with the move once it has been decided, then Pending until the next start
+    fn poll(&mut self) -> Poll<Move>;
+    fn cancel(&mut self);
+}
+
+enum Request {
+    NewGame(Board, Color),
+    OpponentMoved(Move, Board),
+    GameOver(Winner),
+    TimeBudget(Duration),
+    // Decisions are numbered so that the results of cancelled ones can be recognized
+    Decide(u64, Board, Color),
+}
+
+pub struct ThreadedPlayer {
+    requests: Sender<Request>,
+    replies: Receiver<(u64, Move)>,
+    next_id: u64,
+    pending: Option<u64>,
+}
+impl ThreadedPlayer {
+    // The worker thread stops once the ThreadedPlayer is dropped and any decision is finished
+    pub fn new<P: GamePlayer + Send + 'static>(mut player: P) -> ThreadedPlayer {
+        let (requests, request_receiver) = channel();
+        let (reply_sender, replies) = channel();
+        thread::spawn(move || {
+            for request in request_receiver {
+                match request {
+                    Request::NewGame(board, color) => player.new_game(&board, &color),
+                    Request::OpponentMoved(mov, board) => player.opponent_moved(&mov, &board),
+                    Request::GameOver(winner) => player.game_over(&winner),
+                    Request::TimeBudget(budget) => player.set_time_budget(budget),
+                    Request::Decide(id, board, color) => {
+                        let mov = player.decide(&board, &color);
+                        if reply_sender.send((id, mov)).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        ThreadedPlayer {
+            requests,
+            replies,
+            next_id: 0,
+            pending: None,
+        }
+    }
+
+    fn send(&self, request: Request) {
+        self.requests
+            .send(request)
+            .expect("the player thread stopped");
+    }
+}
+impl AsyncGamePlayer for ThreadedPlayer {
+    fn start(&mut self, board: &Board, color: &Color) {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.pending = Some(id);
+        self.send(Request::Decide(id, board.clone(), *color));
+    }
+    fn poll(&mut self) -> Poll<Move> {
+        let Some(pending) = self.pending else {
+            return Poll::Pending;
+        };
+        loop {
+            match self.replies.try_recv() {
+                Ok((id, mov)) if id == pending => {
+                    self.pending = None;
+                    return Poll::Ready(mov);
+                }
+                // A cancelled decision
+                Ok(_) => continue,
+                Err(TryRecvError::Empty) => return Poll::Pending,
+                Err(TryRecvError::Disconnected) => panic!("the player thread stopped"),
+            }
+        }
+    }
+    fn cancel(&mut self) {
+        self.pending = None;
+    }
+}
+impl GamePlayer for ThreadedPlayer {
+    fn decide(&mut self, board: &Board, color: &Color) -> Move {
+        self.start(board, color);
+        loop {
+            let (id, mov) = self.replies.recv().expect("the player thread stopped");
+            if Some(id) == self.pending {
+                self.pending = None;
+                return mov;
+            }
+        }
+    }
+    fn new_game(&mut self, board: &Board, color: &Color) {
+        self.send(Request::NewGame(board.clone(), *color));
+    }
+    fn opponent_moved(&mut self, mov: &Move, board: &Board) {
+        self.send(Request::OpponentMoved(*mov, board.clone()));
+    }
+    fn game_over(&mut self, winner: &Winner) {
+        self.send(Request::GameOver(winner.clone()));
+    }
+    fn set_time_budget(&mut self, budget: Duration) {
+        self.send(Request::TimeBudget(budget));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+
+    // Takes its time, then plays the first legal move
+    struct SlowPlayer {
+        delay: Duration,
+        games: Arc<Mutex<Vec<Winner>>>,
+    }
+    impl GamePlayer for SlowPlayer {
+        fn decide(&mut self, board: &Board, color: &Color) -> Move {
+            thread::sleep(self.delay);
+            board
+                .legal_moves(color)
+                .next()
+                .unwrap_or(Move::Concede(*color))
+        }
+        fn game_over(&mut self, winner: &Winner) {
+            self.games.lock().unwrap().push(winner.clone());
+        }
+        fn set_time_budget(&mut self, budget: Duration) {
+            self.delay = budget;
+        }
+    }
+
+    fn slow_player(delay: Duration) -> (ThreadedPlayer, Arc<Mutex<Vec<Winner>>>) {
+        let games = Arc::new(Mutex::new(vec![]));
+        let player = SlowPlayer {
+            delay,
+            games: games.clone(),
+        };
+        (ThreadedPlayer::new(player), games)
+    }
+
+    fn wait(player: &mut ThreadedPlayer) -> Move {
+        let start = Instant::now();
+        loop {
+            if let Poll::Ready(mov) = player.poll() {
+                return mov;
+            }
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn polls_without_blocking() {
+        let (mut player, _) = slow_player(Duration::from_millis(100));
+        let board = Board::default();
+        assert_eq!(player.poll(), Poll::Pending);
+        let start = Instant::now();
+        player.start(&board, &Color::White);
+        assert_eq!(player.poll(), Poll::Pending);
+        assert!(start.elapsed() < Duration::from_millis(100));
+        let mov = wait(&mut player);
+        assert_eq!(mov, board.legal_moves(&Color::White).next().unwrap());
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        // The move is only handed over once
+        assert_eq!(player.poll(), Poll::Pending);
+    }
+
+    #[test]
+    fn cancelled_moves_are_dropped() {
+        let (mut player, _) = slow_player(Duration::from_millis(20));
+        let board = Board::default();
+        player.start(&board, &Color::White);
+        player.cancel();
+        assert_eq!(player.poll(), Poll::Pending);
+        // Starting again cancels the decision in progress too
+        player.start(&board, &Color::White);
+        player.start(&board, &Color::Black);
+        assert_eq!(
+            wait(&mut player),
+            board.legal_moves(&Color::Black).next().unwrap()
+        );
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(player.poll(), Poll::Pending);
+    }
+
+    #[test]
+    fn plays_games() {
+        let (white, white_games) = slow_player(Duration::ZERO);
+        let (black, black_games) = slow_player(Duration::from_secs(60));
+        let mut game = Game::new(white, black);
+        game.set_time_budget(&Color::Black, Duration::ZERO);
+        let winner = game.finish_game();
+        // game_over is sent after the last move, so give the workers a moment to catch up
+        let start = Instant::now();
+        while black_games.lock().unwrap().is_empty() || white_games.lock().unwrap().is_empty() {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(*white_games.lock().unwrap(), vec![winner.clone()]);
+        assert_eq!(*black_games.lock().unwrap(), vec![winner]);
+    }
+}
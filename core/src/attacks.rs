@@ -0,0 +1,160 @@
+use crate::bitboard::RAYS;
+use crate::{Bitboards, Board, Color, Height, LegalMoveIterator, Move};
+
+/*
+ * Attack maps, showing which squares and pieces could be boomed.
+ *
+ * These follow the same rays as the move generator: a piece attacks the squares it could zoom to,
+ * plus the first opposing piece along each direction within its height. Its own pieces block it.
+ * Like move generation, variants that don't use the standard board fall back to LegalMoveIterator.
+ */
+
+impl Board {
+    // Every square where color could boom an opposing piece, if there was one there. Bit n is the
+    // square with index n.
+    pub fn attacked_squares(&self, color: &Color) -> u64 {
+        if !self.rules.has_standard_board() {
+            return self
+                .legal_moves(color)
+                .filter_map(|mov| self.attacked_square(&mov))
+                .fold(0, |attacked, square| attacked | 1 << square);
+        }
+        let bitboards = self.bitboards();
+        self.rules
+            .pieces(color)
+            .map(|index| self.piece_attacks(&bitboards, index))
+            .fold(0, |attacked, squares| attacked | squares)
+    }
+
+    // The opposing pieces that could boom the piece at piece_index
+    pub fn attackers_of(&self, piece_index: usize) -> impl Iterator<Item = usize> + '_ {
+        let target = &self.pieces[piece_index];
+        let alive = target.height != Height::Dead;
+        let bit = 1u64 << u8::from(target.position);
+        let bitboards = self.bitboards();
+        let standard = self.rules.has_standard_board();
+        self.rules
+            .pieces(&target.color.invert())
+            .filter(move |_| alive)
+            .filter(move |&index| {
+                if standard {
+                    self.piece_attacks(&bitboards, index) & bit != 0
+                } else {
+                    LegalMoveIterator::for_piece(self, index).any(|m| m == Move::Boom(piece_index))
+                }
+            })
+    }
+
+    // The squares that the piece at piece_index attacks
+    fn piece_attacks(&self, bitboards: &Bitboards, piece_index: usize) -> u64 {
+        let piece = &self.pieces[piece_index];
+        let height = u8::from(&piece.height);
+        let ours = bitboards.color(&piece.color);
+        let theirs = bitboards.color(&piece.color.invert());
+        let mut attacked = 0;
+        for ray in RAYS[u8::from(piece.position) as usize].iter() {
+            for &square in ray.squares[..height.min(ray.len) as usize].iter() {
+                let bit = 1 << square;
+                if ours & bit != 0 {
+                    break;
+                }
+                attacked |= bit;
+                if theirs & bit != 0 {
+                    break;
+                }
+            }
+        }
+        attacked
+    }
+
+    fn attacked_square(&self, mov: &Move) -> Option<u8> {
+        match mov {
+            Move::Zoom(_, position) => Some(u8::from(*position)),
+            Move::Boom(index) => Some(u8::from(self.pieces[*index].position)),
+            Move::Score(_) | Move::Concede(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Piece, RuleSet};
+    use rand::prelude::*;
+
+    fn check_against_legal_moves(board: &Board) {
+        for color in [Color::White, Color::Black] {
+            let expected = board
+                .legal_moves(&color)
+                .filter_map(|mov| board.attacked_square(&mov))
+                .fold(0, |attacked, square| attacked | 1u64 << square);
+            assert_eq!(board.attacked_squares(&color), expected, "{board:?}");
+        }
+        for target in 0..board.rules.piece_count() {
+            let color = board.pieces[target].color.invert();
+            let expected: Vec<usize> = board
+                .rules
+                .pieces(&color)
+                .filter(|&index| {
+                    LegalMoveIterator::for_piece(board, index).any(|m| m == Move::Boom(target))
+                })
+                .collect();
+            assert_eq!(
+                board.attackers_of(target).collect::<Vec<usize>>(),
+                expected,
+                "{board:?}"
+            );
+            assert_eq!(
+                board.boomable(target),
+                board.legal_moves(&color).any(|m| m == Move::Boom(target))
+            );
+        }
+    }
+
+    #[test]
+    fn matches_legal_moves() {
+        let mut rng = thread_rng();
+        for rules in [RuleSet::STANDARD, RuleSet::new(6, 5, 3)] {
+            for _ in 0..25 {
+                let mut board = Board::new(rules);
+                let mut turn = Color::White;
+                while board.winner().is_none() {
+                    check_against_legal_moves(&board);
+                    let Some(mov) = board.legal_moves(&turn).choose(&mut rng) else {
+                        break;
+                    };
+                    board = board.apply_move(&mov);
+                    turn = turn.invert();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn boom_geometry() {
+        let piece = |square: &str, height| Piece {
+            color: Color::White,
+            position: square.try_into().unwrap(),
+            height,
+        };
+        let boomer = piece("d4", Height::Two);
+        for (square, expected) in [
+            ("b2", true),
+            ("f2", true),
+            ("b6", true),
+            ("f6", true),
+            ("d2", true),
+            ("b4", true),
+            ("a1", false),
+            ("e6", false),
+            ("d4", false),
+        ] {
+            let other = piece(square, Height::One);
+            assert_eq!(boomer.can_boom(&other), expected, "{square}");
+        }
+        // A piece on the far side of a blocker can't be boomed
+        let (board, _) = Board::from_fen("8/8/8/8/3b34/3w34/3w34/8 w 0 0 -").unwrap();
+        assert_eq!(board.attackers_of(4).collect::<Vec<usize>>(), vec![1]);
+        assert!(board.pieces[0].can_boom(&board.pieces[4]));
+    }
+}
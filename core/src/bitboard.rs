@@ -0,0 +1,209 @@
+use crate::{Board, Color, Height, LegalMoveIterator, Move, Position, DIRECTIONS};
+
+/*
+ * Bitboard move generation.
+ *
+ * Bit n of a bitboard is the square with index n, so a1 is bit 0 and h8 is bit 63. For every square
+ * and direction there is a precomputed ray of the (up to 3) squares a piece could travel through,
+ * and the distance to the score zone if the ray runs off the top or bottom of the board into it.
+ *
+ * The moves generated are exactly the same, and in the same order, as LegalMoveIterator. The rays
+ * are only precomputed for the standard board, so variants fall back to LegalMoveIterator.
+ *
+ * Moves can be generated into anything that can be extended with them, like a Vec or a MoveList.
+ */
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Bitboards {
+    pub white: u64,
+    pub black: u64,
+}
+impl Bitboards {
+    pub fn occupied(&self) -> u64 {
+        self.white | self.black
+    }
+    pub fn color(&self, color: &Color) -> u64 {
+        match color {
+            Color::White => self.white,
+            Color::Black => self.black,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct Ray {
+    pub(crate) squares: [u8; 3],
+    pub(crate) len: u8,
+    // The color that scores by leaving the board at the end of this ray, if any
+    score_zone: Option<Color>,
+}
+
+const fn generate_rays() -> [[Ray; 8]; 64] {
+    let empty = Ray {
+        squares: [0; 3],
+        len: 0,
+        score_zone: None,
+    };
+    let mut rays = [[empty; 8]; 64];
+    let mut square = 0;
+    while square < 64 {
+        let mut dir = 0;
+        while dir < 8 {
+            let (dx, dy) = DIRECTIONS[dir];
+            let mut ray = empty;
+            let (mut x, mut y) = ((square % 8) as i8, (square / 8) as i8);
+            while ray.len < 3 {
+                x += dx;
+                y += dy;
+                // This mirrors Position::offset
+                if y == 8 && x >= -1 && x <= 8 {
+                    ray.score_zone = Some(Color::White);
+                    break;
+                } else if y == -1 && x >= -1 && x <= 8 {
+                    ray.score_zone = Some(Color::Black);
+                    break;
+                } else if x < 0 || x >= 8 || y < 0 || y >= 8 {
+                    break;
+                }
+                ray.squares[ray.len as usize] = (x + y * 8) as u8;
+                ray.len += 1;
+            }
+            rays[square][dir] = ray;
+            dir += 1;
+        }
+        square += 1;
+    }
+    rays
+}
+
+pub(crate) static RAYS: [[Ray; 8]; 64] = generate_rays();
+
+impl Board {
+    pub fn bitboards(&self) -> Bitboards {
+        let mut bitboards = Bitboards::default();
+        for piece in self.pieces.iter().filter(|p| p.height != Height::Dead) {
+            let bit = 1 << u8::from(piece.position);
+            match piece.color {
+                Color::White => bitboards.white |= bit,
+                Color::Black => bitboards.black |= bit,
+            }
+        }
+        bitboards
+    }
+
+    // Appends every legal move for color to moves
+    pub fn generate_moves(&self, color: &Color, moves: &mut impl Extend<Move>) {
+        if !self.rules.has_standard_board() {
+            moves.extend(self.legal_moves(color));
+            return;
+        }
+        let bitboards = self.bitboards();
+        for piece_index in self.rules.pieces(color) {
+            self.generate_piece_moves(&bitboards, piece_index, moves);
+        }
+    }
+
+    // Appends every legal move for the piece at piece_index to moves
+    pub fn generate_moves_for(&self, piece_index: usize, moves: &mut impl Extend<Move>) {
+        if !self.rules.has_standard_board() {
+            moves.extend(LegalMoveIterator::for_piece(self, piece_index));
+            return;
+        }
+        self.generate_piece_moves(&self.bitboards(), piece_index, moves);
+    }
+
+    fn generate_piece_moves(
+        &self,
+        bitboards: &Bitboards,
+        piece_index: usize,
+        moves: &mut impl Extend<Move>,
+    ) {
+        let piece = &self.pieces[piece_index];
+        let height = u8::from(&piece.height);
+        let ours = bitboards.color(&piece.color);
+        let theirs = bitboards.color(&piece.color.invert());
+        let mut has_scored = false;
+        for ray in RAYS[u8::from(piece.position) as usize].iter() {
+            let reach = height.min(ray.len);
+            let mut blocked = false;
+            for &square in ray.squares[..reach as usize].iter() {
+                let bit = 1 << square;
+                if ours & bit != 0 {
+                    blocked = true;
+                    break;
+                }
+                if theirs & bit != 0 {
+                    moves.extend([Move::Boom(self.piece_index_at(square))]);
+                    blocked = true;
+                    break;
+                }
+                moves.extend([Move::Zoom(piece_index, Position::from(square))]);
+            }
+            if !blocked && !has_scored && ray.len < height && ray.score_zone == Some(piece.color) {
+                has_scored = true;
+                moves.extend([Move::Score(piece_index)]);
+            }
+        }
+    }
+
+    // Only call this for squares known to be occupied
+    fn piece_index_at(&self, square: u8) -> usize {
+        self.get_piece_at(&Position::from(square))
+            .expect("the square is occupied")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn matches_legal_moves() {
+        let mut rng = thread_rng();
+        for _ in 0..100 {
+            let mut board = Board::default();
+            let mut turn = Color::White;
+            while board.winner().is_none() {
+                for color in [Color::White, Color::Black] {
+                    let mut moves = vec![];
+                    board.generate_moves(&color, &mut moves);
+                    assert_eq!(moves, board.legal_moves(&color).collect::<Vec<Move>>());
+                }
+                for piece_index in 0..8 {
+                    let mut moves = vec![];
+                    board.generate_moves_for(piece_index, &mut moves);
+                    assert_eq!(
+                        moves,
+                        LegalMoveIterator::for_piece(&board, piece_index).collect::<Vec<Move>>()
+                    );
+                }
+                let Some(mov) = board.legal_moves(&turn).choose(&mut rng) else {
+                    break;
+                };
+                board = board.apply_move(&mov);
+                turn = turn.invert();
+            }
+        }
+    }
+
+    #[test]
+    fn every_piece_can_score() {
+        let (board, _) = Board::from_fen("w1w1w1w14/8/8/8/8/8/8/7b1 w 0 0 -").unwrap();
+        let mut moves = vec![];
+        board.generate_moves(&Color::White, &mut moves);
+        let scores: Vec<&Move> = moves
+            .iter()
+            .filter(|m| matches!(m, Move::Score(_)))
+            .collect();
+        assert_eq!(
+            scores,
+            vec![
+                &Move::Score(0),
+                &Move::Score(1),
+                &Move::Score(2),
+                &Move::Score(3)
+            ]
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use crate::{Board, Color, Height, Piece, Position, RuleSet};
+
+/*
+ * Building positions piece by piece, without breaking the board's invariants.
+ *
+ *   let board = BoardBuilder::new()
+ *       .piece(Color::White, "c4", Height::Three)
+ *       .piece(Color::Black, "d5", Height::One)
+ *       .scores(2, 0)
+ *       .build()?;
+ *
+ * Each color's pieces get their index slots in the order they were added, so the first white piece
+ * is always piece 0 and the first black piece is always piece rules.pieces_per_side. The built
+ * board's zobrist key is for the side to move, which is White unless BoardBuilder::turn says
+ * otherwise.
+ */
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BuildError {
+    InvalidSquare(String),
+    OffBoard(Position),
+    DeadPiece(Position),
+    Overlapping(Position),
+    TooManyPieces(Color),
+    // The score plus the pieces left on the board is more than the color started with
+    ImpossibleScore(Color),
+}
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::InvalidSquare(square) => write!(f, "\"{square}\" is not a square"),
+            BuildError::OffBoard(position) => write!(f, "{position} is not on the board"),
+            BuildError::DeadPiece(position) => write!(f, "The piece on {position} is dead"),
+            BuildError::Overlapping(position) => {
+                write!(f, "There is more than one piece on {position}")
+            }
+            BuildError::TooManyPieces(color) => write!(f, "{color:?} has too many pieces"),
+            BuildError::ImpossibleScore(color) => {
+                write!(f, "{color:?} has more points than it started with")
+            }
+        }
+    }
+}
+impl std::error::Error for BuildError {}
+
+#[derive(Clone, Debug, Default)]
+pub struct BoardBuilder {
+    rules: RuleSet,
+    pieces: Vec<Result<Piece, BuildError>>,
+    white_score: u8,
+    black_score: u8,
+    victory_by_concession: Option<Color>,
+    turn: Option<Color>,
+}
+impl BoardBuilder {
+    pub fn new() -> BoardBuilder {
+        BoardBuilder::default()
+    }
+
+    pub fn with_rules(rules: RuleSet) -> BoardBuilder {
+        BoardBuilder {
+            rules,
+            ..BoardBuilder::default()
+        }
+    }
+
+    // Places a piece on a square given by name, like "c4"
+    pub fn piece(self, color: Color, square: &str, height: Height) -> BoardBuilder {
+        match Position::try_from(square) {
+            Ok(position) => self.piece_at(color, position, height),
+            Err(()) => self.push(Err(BuildError::InvalidSquare(square.to_string()))),
+        }
+    }
+
+    pub fn piece_at(self, color: Color, position: Position, height: Height) -> BoardBuilder {
+        self.push(Ok(Piece {
+            color,
+            position,
+            height,
+        }))
+    }
+
+    pub fn scores(mut self, white_score: u8, black_score: u8) -> BoardBuilder {
+        self.white_score = white_score;
+        self.black_score = black_score;
+        self
+    }
+
+    pub fn conceded_by(mut self, color: Color) -> BoardBuilder {
+        self.victory_by_concession = Some(color.invert());
+        self
+    }
+
+    pub fn turn(mut self, turn: Color) -> BoardBuilder {
+        self.turn = Some(turn);
+        self
+    }
+
+    // Errors are kept until build, so that pieces can be chained without unwrapping
+    fn push(mut self, piece: Result<Piece, BuildError>) -> BoardBuilder {
+        self.pieces.push(piece);
+        self
+    }
+
+    pub fn build(self) -> Result<Board, BuildError> {
+        let rules = self.rules;
+        let mut board = Board::empty(rules);
+        let mut white_slots = rules.pieces(&Color::White);
+        let mut black_slots = rules.pieces(&Color::Black);
+        for piece in self.pieces {
+            let piece = piece?;
+            let position = piece.position;
+            if !rules.contains(position.x(), position.y()) {
+                return Err(BuildError::OffBoard(position));
+            }
+            if piece.height == Height::Dead {
+                return Err(BuildError::DeadPiece(position));
+            }
+            if board.get_piece_at(&position).is_some() {
+                return Err(BuildError::Overlapping(position));
+            }
+            let slot = match piece.color {
+                Color::White => white_slots.next(),
+                Color::Black => black_slots.next(),
+            };
+            let slot = slot.ok_or(BuildError::TooManyPieces(piece.color))?;
+            board.pieces[slot] = piece;
+        }
+        board.white_score = self.white_score;
+        board.black_score = self.black_score;
+        board.victory_by_concession = self.victory_by_concession;
+        let starting: u16 = rules.start_heights[..rules.pieces_per_side]
+            .iter()
+            .map(|h| u8::from(h) as u16)
+            .sum();
+        for (color, score) in [
+            (Color::White, self.white_score),
+            (Color::Black, self.black_score),
+        ] {
+            let remaining: u16 = board.pieces[rules.pieces(&color)]
+                .iter()
+                .map(|p| u8::from(&p.height) as u16)
+                .sum();
+            if score as u16 + remaining > starting {
+                return Err(BuildError::ImpossibleScore(color));
+            }
+        }
+        board.rehash(&self.turn.unwrap_or(Color::White));
+        Ok(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_positions() {
+        let board = BoardBuilder::new()
+            .piece(Color::Black, "d5", Height::One)
+            .piece(Color::White, "c4", Height::Three)
+            .piece(Color::White, "a1", Height::Two)
+            .scores(2, 0)
+            .turn(Color::Black)
+            .build()
+            .unwrap();
+        assert_eq!(board.pieces[0].position, "c4".try_into().unwrap());
+        assert_eq!(board.pieces[1].height, Height::Two);
+        assert_eq!(board.pieces[2].height, Height::Dead);
+        assert_eq!(board.pieces[4].position, "d5".try_into().unwrap());
+        assert_eq!(board.pieces[4].color, Color::Black);
+        assert_eq!(board.white_score, 2);
+        assert_eq!(
+            board.to_fen(&Color::Black),
+            "8/8/8/3b14/2w35/8/8/w27 b 2 0 - 0 0"
+        );
+        assert_eq!(board.zobrist(), board.compute_zobrist(&Color::Black));
+
+        let empty = BoardBuilder::new()
+            .conceded_by(Color::White)
+            .build()
+            .unwrap();
+        assert_eq!(empty.zobrist(), empty.compute_zobrist(&Color::White));
+        assert_eq!(empty.victory_by_concession, Some(Color::Black));
+    }
+
+    #[test]
+    fn builds_variants() {
+        let rules = RuleSet::new(6, 6, 5);
+        let builder = BoardBuilder::with_rules(rules);
+        let board = builder
+            .clone()
+            .piece(Color::Black, "f6", Height::Three)
+            .build()
+            .unwrap();
+        assert_eq!(board.pieces[5].position, "f6".try_into().unwrap());
+        assert_eq!(
+            builder.piece(Color::White, "g1", Height::One).build().err(),
+            Some(BuildError::OffBoard("g1".try_into().unwrap()))
+        );
+    }
+
+    #[test]
+    fn build_errors() {
+        let builder = BoardBuilder::new();
+        assert_eq!(
+            builder
+                .clone()
+                .piece(Color::White, "c4", Height::Three)
+                .piece(Color::Black, "c4", Height::One)
+                .build()
+                .err(),
+            Some(BuildError::Overlapping("c4".try_into().unwrap()))
+        );
+        assert_eq!(
+            builder
+                .clone()
+                .piece(Color::White, "a1", Height::One)
+                .piece(Color::White, "b1", Height::One)
+                .piece(Color::White, "c1", Height::One)
+                .piece(Color::White, "d1", Height::One)
+                .piece(Color::White, "e1", Height::One)
+                .build()
+                .err(),
+            Some(BuildError::TooManyPieces(Color::White))
+        );
+        assert_eq!(
+            builder
+                .clone()
+                .piece(Color::Black, "c9", Height::One)
+                .build()
+                .err(),
+            Some(BuildError::InvalidSquare("c9".to_string()))
+        );
+        assert_eq!(
+            builder
+                .clone()
+                .piece(Color::Black, "c3", Height::Dead)
+                .build()
+                .err(),
+            Some(BuildError::DeadPiece("c3".try_into().unwrap()))
+        );
+        assert_eq!(
+            builder
+                .clone()
+                .piece(Color::Black, "c3", Height::Three)
+                .scores(0, 10)
+                .build()
+                .err(),
+            Some(BuildError::ImpossibleScore(Color::Black))
+        );
+        assert!(builder.scores(12, 12).build().is_ok());
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{Board, Winner};
+
+/*
+ * Draw rules.
+ *
+ * Without these, two players can shuffle their pieces back and forth forever. A game is drawn when:
+ *   - it has gone on for max_plies plies,
+ *   - no_progress_plies plies have passed without anything being boomed or scored, or
+ *   - the same position has come up repetitions times with the same side to move.
+ *
+ * The board keeps its own ply counters, so it can tell when the first two have happened. Boards
+ * don't know what came before them though, so repetition is only checked by Game, or by anyone
+ * else keeping track of the keys of earlier positions.
+ */
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct DrawRules {
+    pub max_plies: Option<u16>,
+    pub no_progress_plies: Option<u16>,
+    pub repetitions: Option<usize>,
+}
+impl DrawRules {
+    pub const STANDARD: DrawRules = DrawRules {
+        max_plies: None,
+        no_progress_plies: Some(100),
+        repetitions: Some(3),
+    };
+    // Games only end when somebody runs out of pieces or concedes
+    pub const NONE: DrawRules = DrawRules {
+        max_plies: None,
+        no_progress_plies: None,
+        repetitions: None,
+    };
+}
+impl Default for DrawRules {
+    fn default() -> DrawRules {
+        DrawRules::STANDARD
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DrawReason {
+    // The game ended normally with both sides on the same score
+    EqualScores,
+    MaxPlies,
+    NoProgress,
+    Repetition,
+}
+impl std::fmt::Display for DrawReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DrawReason::EqualScores => write!(f, "Both sides scored the same"),
+            DrawReason::MaxPlies => write!(f, "Too many moves were played"),
+            DrawReason::NoProgress => write!(f, "Nothing was boomed or scored for too long"),
+            DrawReason::Repetition => write!(f, "The same position came up too many times"),
+        }
+    }
+}
+
+impl Board {
+    // Why the board is drawn, if it is drawn. This can't detect repetition.
+    pub fn draw_reason(&self) -> Option<DrawReason> {
+        match self.scored_winner() {
+            Some(Winner::Draw) => Some(DrawReason::EqualScores),
+            Some(_) => None,
+            None => self.draw_limit(),
+        }
+    }
+
+    // Checks the move counters against the draw rules
+    pub(crate) fn draw_limit(&self) -> Option<DrawReason> {
+        let draws = &self.rules.draws;
+        if draws.max_plies.is_some_and(|max| self.plies_played >= max) {
+            Some(DrawReason::MaxPlies)
+        } else if draws
+            .no_progress_plies
+            .is_some_and(|max| self.plies_since_progress >= max)
+        {
+            Some(DrawReason::NoProgress)
+        } else {
+            None
+        }
+    }
+
+    // Whether this position has now been repeated too many times. earlier is the keys of the
+    // positions before this one, most recent first.
+    pub fn is_repetition(&self, earlier: impl IntoIterator<Item = u64>) -> bool {
+        let Some(repetitions) = self.rules.draws.repetitions else {
+            return false;
+        };
+        // Booms and scores can't be undone, so there's no need to look back further than that
+        let occurrences = earlier
+            .into_iter()
+            .take(self.plies_since_progress as usize)
+            .filter(|&key| key == self.zobrist())
+            .count()
+            + 1;
+        occurrences >= repetitions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Move, RuleSet};
+
+    fn square(s: &str) -> crate::Position {
+        s.try_into().unwrap()
+    }
+
+    #[test]
+    fn no_progress() {
+        let mut board = Board::default();
+        for i in 0..100 {
+            assert_eq!(board.winner(), None);
+            let mov = match i % 4 {
+                0 => Move::Zoom(0, square("c2")),
+                1 => Move::Zoom(4, square("c7")),
+                2 => Move::Zoom(0, square("c1")),
+                _ => Move::Zoom(4, square("c8")),
+            };
+            board = board.apply_move(&mov);
+        }
+        assert_eq!(board.winner(), Some(Winner::Draw));
+        assert_eq!(board.draw_reason(), Some(DrawReason::NoProgress));
+    }
+
+    #[test]
+    fn progress_resets_the_counter() {
+        let mut board = Board::default();
+        board.pieces[4].position = square("c4");
+        let board = board
+            .apply_move(&Move::Zoom(1, square("d2")))
+            .apply_move(&Move::Zoom(5, square("d7")));
+        assert_eq!(board.plies_since_progress, 2);
+        let board = board.apply_move(&Move::Boom(4));
+        assert_eq!(board.plies_since_progress, 0);
+        assert_eq!(board.plies_played, 3);
+    }
+
+    #[test]
+    fn max_plies() {
+        let rules = RuleSet::STANDARD.with_draw_rules(DrawRules {
+            max_plies: Some(2),
+            ..DrawRules::NONE
+        });
+        let board = Board::new(rules).apply_move(&Move::Zoom(0, square("c2")));
+        assert_eq!(board.winner(), None);
+        let board = board.apply_move(&Move::Zoom(4, square("c7")));
+        assert_eq!(board.winner(), Some(Winner::Draw));
+        assert_eq!(board.draw_reason(), Some(DrawReason::MaxPlies));
+    }
+
+    #[test]
+    fn equal_scores() {
+        let (board, _) = Board::from_fen("8/8/8/8/8/8/8/8 w 3 3 -").unwrap();
+        assert_eq!(board.draw_reason(), Some(DrawReason::EqualScores));
+        let (board, _) = Board::from_fen("8/8/8/8/8/8/8/8 w 3 2 -").unwrap();
+        assert_eq!(board.draw_reason(), None);
+        let conceded = Board::default().apply_move(&Move::Concede(Color::White));
+        assert_eq!(conceded.draw_reason(), None);
+    }
+
+    #[test]
+    fn threefold_repetition() {
+        let mut board = Board::default();
+        let mut keys = vec![];
+        let shuffle = [
+            Move::Zoom(0, square("c2")),
+            Move::Zoom(4, square("c7")),
+            Move::Zoom(0, square("c1")),
+            Move::Zoom(4, square("c8")),
+        ];
+        for mov in shuffle.iter().chain(shuffle.iter()) {
+            assert!(!board.is_repetition(keys.iter().rev().copied()));
+            keys.push(board.zobrist());
+            board = board.apply_move(mov);
+        }
+        // The starting position, for the third time
+        assert!(board.is_repetition(keys.iter().rev().copied()));
+    }
+}
@@ -0,0 +1,315 @@
+use crate::{Board, Color, Height, Piece, RuleSet};
+
+/*
+ * Position strings, in the spirit of chess FEN.
+ *
+ *   2b3b3b3b32/8/8/8/8/8/8/2w3w3w3w32 w 0 0 - 0 0
+ *
+ * The fields are, in order:
+ *   1. Piece placement, from rank 8 down to rank 1, ranks separated by '/'. A piece is written as
+ *      its color (w or b) followed by its height (1-3). A digit on its own is a run of empty
+ *      squares.
+ *   2. The side to move, w or b.
+ *   3. White's score.
+ *   4. Black's score.
+ *   5. The color that has won by concession (w or b), or - if nobody has conceded.
+ *   6. The plies since the last boom or score, for the no progress draw rule.
+ *   7. The plies played so far, for the turn limit.
+ *
+ * Like the clocks at the end of a chess FEN, the last two fields can be left off, and are then
+ * taken to be 0.
+ *
+ * Boards with smaller variant rules have fewer and shorter ranks, and must be parsed with
+ * Board::from_fen_with_rules.
+ *
+ * Dead pieces are not written. When parsing, the living pieces of each color are assigned their
+ * index slots rank by rank from rank 1, then file by file from the a file, and any unused slots
+ * are filled with dead pieces.
+ */
+
+pub const STARTING_FEN: &str = "2b3b3b3b32/8/8/8/8/8/8/2w3w3w3w32 w 0 0 - 0 0";
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FenError {
+    WrongFieldCount(usize),
+    WrongRankCount(usize),
+    BadRank(String),
+    TooManyPieces(Color),
+    BadColor(String),
+    BadScore(String),
+    BadCounter(String),
+}
+impl std::fmt::Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FenError::WrongFieldCount(count) => {
+                write!(f, "Expected 5 or 7 fields, found {count}")
+            }
+            FenError::WrongRankCount(count) => write!(f, "Wrong number of ranks: {count}"),
+            FenError::BadRank(rank) => write!(f, "Invalid rank \"{rank}\""),
+            FenError::TooManyPieces(color) => write!(f, "{color:?} has too many pieces"),
+            FenError::BadColor(color) => write!(f, "Invalid color \"{color}\""),
+            FenError::BadScore(score) => write!(f, "Invalid score \"{score}\""),
+            FenError::BadCounter(counter) => write!(f, "Invalid ply counter \"{counter}\""),
+        }
+    }
+}
+impl std::error::Error for FenError {}
+
+fn color_char(color: &Color) -> char {
+    match color {
+        Color::White => 'w',
+        Color::Black => 'b',
+    }
+}
+
+fn parse_color(s: &str) -> Result<Color, FenError> {
+    match s {
+        "w" => Ok(Color::White),
+        "b" => Ok(Color::Black),
+        _ => Err(FenError::BadColor(s.to_string())),
+    }
+}
+
+fn parse_score(s: &str) -> Result<u8, FenError> {
+    s.parse().map_err(|_| FenError::BadScore(s.to_string()))
+}
+
+fn parse_counter(s: &str) -> Result<u16, FenError> {
+    s.parse().map_err(|_| FenError::BadCounter(s.to_string()))
+}
+
+// Parses a single rank into (x, color, height) triples
+fn parse_rank(rank: &str, width: i8) -> Result<Vec<(i8, Color, Height)>, FenError> {
+    let bad_rank = || FenError::BadRank(rank.to_string());
+    let mut pieces = vec![];
+    let mut x = 0;
+    let mut chars = rank.chars();
+    while let Some(c) = chars.next() {
+        if let Some(empty) = c.to_digit(10) {
+            if empty == 0 {
+                return Err(bad_rank());
+            }
+            x += empty as i8;
+        } else {
+            let color = parse_color(&c.to_string()).map_err(|_| bad_rank())?;
+            let height = chars
+                .next()
+                .and_then(|h| h.to_digit(10))
+                .and_then(|h| Height::try_from(h as u8).ok())
+                .filter(|h| h != &Height::Dead)
+                .ok_or_else(bad_rank)?;
+            pieces.push((x, color, height));
+            x += 1;
+        }
+        if x > width {
+            return Err(bad_rank());
+        }
+    }
+    if x != width {
+        return Err(bad_rank());
+    }
+    Ok(pieces)
+}
+
+impl Board {
+    pub fn to_fen(&self, turn: &Color) -> String {
+        let ranks: Vec<String> = (0..self.rules.height)
+            .rev()
+            .map(|y| {
+                let mut rank = String::new();
+                let mut empty = 0;
+                for x in 0..self.rules.width {
+                    if let Some(index) = self.get_piece_at(&(x, y).into()) {
+                        if empty > 0 {
+                            rank += &empty.to_string();
+                            empty = 0;
+                        }
+                        let piece = &self.pieces[index];
+                        rank.push(color_char(&piece.color));
+                        rank += &u8::from(&piece.height).to_string();
+                    } else {
+                        empty += 1;
+                    }
+                }
+                if empty > 0 {
+                    rank += &empty.to_string();
+                }
+                rank
+            })
+            .collect();
+        let concession = match &self.victory_by_concession {
+            Some(color) => color_char(color),
+            None => '-',
+        };
+        format!(
+            "{} {} {} {} {} {} {}",
+            ranks.join("/"),
+            color_char(turn),
+            self.white_score,
+            self.black_score,
+            concession,
+            self.plies_since_progress,
+            self.plies_played
+        )
+    }
+
+    // The pieces are put in fresh slots, so a Move from before a round trip through FEN might refer
+    // to a different piece afterwards
+    pub fn from_fen(fen: &str) -> Result<(Board, Color), FenError> {
+        Board::from_fen_with_rules(fen, RuleSet::STANDARD)
+    }
+
+    pub fn from_fen_with_rules(fen: &str, rules: RuleSet) -> Result<(Board, Color), FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() != 5 && fields.len() != 7 {
+            return Err(FenError::WrongFieldCount(fields.len()));
+        }
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != rules.height as usize {
+            return Err(FenError::WrongRankCount(ranks.len()));
+        }
+        let mut whites = vec![];
+        let mut blacks = vec![];
+        // Ranks are written from 8 down to 1, but slots are assigned from rank 1 upwards
+        for (y, rank) in ranks.iter().rev().enumerate() {
+            for (x, color, height) in parse_rank(rank, rules.width)? {
+                let piece = Piece {
+                    color,
+                    position: (x, y as i8).into(),
+                    height,
+                };
+                match color {
+                    Color::White => whites.push(piece),
+                    Color::Black => blacks.push(piece),
+                }
+            }
+        }
+        for (color, pieces) in [(Color::White, &whites), (Color::Black, &blacks)] {
+            if pieces.len() > rules.pieces_per_side {
+                return Err(FenError::TooManyPieces(color));
+            }
+        }
+        let turn = parse_color(fields[1])?;
+        let white_score = parse_score(fields[2])?;
+        let black_score = parse_score(fields[3])?;
+        let victory_by_concession = match fields[4] {
+            "-" => None,
+            color => Some(parse_color(color)?),
+        };
+        let (plies_since_progress, plies_played) = match fields.get(5..) {
+            Some([since_progress, played]) => {
+                (parse_counter(since_progress)?, parse_counter(played)?)
+            }
+            _ => (0, 0),
+        };
+        let mut board = Board::empty(rules);
+        for (color, pieces) in [(Color::White, whites), (Color::Black, blacks)] {
+            for (index, piece) in rules.pieces(&color).zip(pieces) {
+                board.pieces[index] = piece;
+            }
+        }
+        board.white_score = white_score;
+        board.black_score = black_score;
+        board.victory_by_concession = victory_by_concession;
+        board.plies_since_progress = plies_since_progress;
+        board.plies_played = plies_played;
+        board.rehash(&turn);
+        Ok((board, turn))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starting_fen() {
+        assert_eq!(Board::default().to_fen(&Color::White), STARTING_FEN);
+        let (board, turn) = Board::from_fen(STARTING_FEN).unwrap();
+        assert_eq!(turn, Color::White);
+        assert_eq!(board.compress(), Board::default().compress());
+    }
+
+    #[test]
+    fn fen_round_trip() {
+        let fen = "8/3b2w13/8/1b1w35/8/8/8/7b3 b 4 1 - 12 57";
+        let (board, turn) = Board::from_fen(fen).unwrap();
+        assert_eq!(turn, Color::Black);
+        assert_eq!(board.white_score, 4);
+        assert_eq!(board.black_score, 1);
+        assert_eq!(board.plies_since_progress, 12);
+        assert_eq!(board.plies_played, 57);
+        assert_eq!(board.pieces[0].height, Height::Three);
+        assert_eq!(board.pieces[1].height, Height::One);
+        assert_eq!(board.pieces[2].height, Height::Dead);
+        assert_eq!(board.pieces[4].position, "h1".try_into().unwrap());
+        assert_eq!(board.to_fen(&turn), fen);
+
+        let conceded = board.apply_move(&crate::Move::Concede(Color::White));
+        let fen = conceded.to_fen(&Color::White);
+        assert!(fen.ends_with(" b 13 58"));
+        let (board, _) = Board::from_fen(&fen).unwrap();
+        assert_eq!(board.victory_by_concession, Some(Color::Black));
+
+        // The draw counters can be left off
+        let (board, _) = Board::from_fen("8/3b2w13/8/1b1w35/8/8/8/7b3 b 4 1 -").unwrap();
+        assert_eq!(board.plies_since_progress, 0);
+        assert_eq!(board.plies_played, 0);
+    }
+
+    #[test]
+    fn variant_fen() {
+        let rules = RuleSet::new(6, 6, 5);
+        let board = Board::new(rules);
+        let fen = board.to_fen(&Color::White);
+        assert_eq!(fen, "b3b3b3b3b31/6/6/6/6/w3w3w3w3w31 w 0 0 - 0 0");
+        let (parsed, _) = Board::from_fen_with_rules(&fen, rules).unwrap();
+        assert_eq!(parsed.rules, rules);
+        assert_eq!(parsed.zobrist(), board.zobrist());
+        assert_eq!(
+            Board::from_fen(&fen).err(),
+            Some(FenError::WrongRankCount(6))
+        );
+    }
+
+    #[test]
+    fn fen_errors() {
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/8 w 0 0").err(),
+            Some(FenError::WrongFieldCount(4))
+        );
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/8 w 0 0 - 0").err(),
+            Some(FenError::WrongFieldCount(6))
+        );
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8 w 0 0 -").err(),
+            Some(FenError::WrongRankCount(7))
+        );
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/7w4 w 0 0 -").err(),
+            Some(FenError::BadRank("7w4".to_string()))
+        );
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/w07 w 0 0 -").err(),
+            Some(FenError::BadRank("w07".to_string()))
+        );
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/w1w1w1w1w13 w 0 0 -").err(),
+            Some(FenError::TooManyPieces(Color::White))
+        );
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/8 x 0 0 -").err(),
+            Some(FenError::BadColor("x".to_string()))
+        );
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/8 w 0 300 -").err(),
+            Some(FenError::BadScore("300".to_string()))
+        );
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/8 w 0 0 - -1 0").err(),
+            Some(FenError::BadCounter("-1".to_string()))
+        );
+    }
+}
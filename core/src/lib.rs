@@ -1,6 +1,45 @@
 use std::str::FromStr;
+use std::time::Duration;
+
+mod async_player;
+mod attacks;
+mod bitboard;
+mod builder;
+mod draw;
+mod fen;
+mod move_encoding;
+mod move_list;
+mod notation;
+mod observer;
+mod packed;
+mod perft;
+mod record;
+mod rules;
+#[cfg(feature = "serde")]
+mod serialize;
+mod symmetry;
+mod undo;
+mod validate;
+mod zobrist;
+
+pub use async_player::{AsyncGamePlayer, ThreadedPlayer};
+pub use bitboard::Bitboards;
+pub use builder::{BoardBuilder, BuildError};
+pub use draw::{DrawReason, DrawRules};
+pub use fen::{FenError, STARTING_FEN};
+pub use move_list::MoveList;
+pub use notation::NotationError;
+pub use observer::GameObserver;
+pub use packed::{PackError, PackedBoard};
+pub use record::{GameRecord, RecordError};
+pub use rules::{RuleSet, MAX_PIECES, MAX_PIECES_PER_SIDE};
+pub use symmetry::Symmetry;
+pub use undo::Undo;
+pub use validate::MoveError;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Color {
     White,
     Black,
@@ -28,6 +67,8 @@ impl FromStr for Color {
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Winner {
     White,
     Black,
@@ -89,6 +130,11 @@ impl std::fmt::Debug for Position {
         f.write_str(&format!("{}{}", columns[self.x as usize], self.y + 1))
     }
 }
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
 impl TryFrom<&str> for Position {
     type Error = ();
     fn try_from(value: &str) -> Result<Self, Self::Error> {
@@ -100,23 +146,32 @@ impl TryFrom<&str> for Position {
             Err(())
         } else {
             let x = columns.iter().position(|cname| cname == &col).ok_or(())?;
-            let y = row.to_digit(10).ok_or(())? - 1;
+            let y = row.to_digit(10).filter(|y| (1..=8).contains(y)).ok_or(())? - 1;
             Ok((x as i8, y as i8).into())
         }
     }
 }
 impl Position {
-    pub fn offset(&self, dx: i8, dy: i8) -> PositionOffset {
+    pub fn offset(&self, dx: i8, dy: i8, rules: &RuleSet) -> PositionOffset {
         let x = self.x + dx;
         let y = self.y + dy;
         let index = x + (y * 8);
-        if y == -1 && (-1..9).contains(&x) {
+        let on_edge = (-1..=rules.width).contains(&x);
+        if y == -1 && on_edge {
             // Meaning black gets points for going here
-            PositionOffset::ScoreZone(Color::Black)
-        } else if y == 8 && (-1..9).contains(&x) {
+            if rules.score_from_far_rank_only && self.y != 0 {
+                PositionOffset::Invalid
+            } else {
+                PositionOffset::ScoreZone(Color::Black)
+            }
+        } else if y == rules.height && on_edge {
             // Meaning white gets points for going here
-            PositionOffset::ScoreZone(Color::White)
-        } else if !(0..8).contains(&x) || !(0..8).contains(&y) {
+            if rules.score_from_far_rank_only && self.y != rules.height - 1 {
+                PositionOffset::Invalid
+            } else {
+                PositionOffset::ScoreZone(Color::White)
+            }
+        } else if !rules.contains(x, y) {
             PositionOffset::Invalid
         } else {
             PositionOffset::Valid(Position { x, y, index })
@@ -138,7 +193,7 @@ pub enum PositionOffset {
     Invalid,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum Height {
     Dead,
     One,
@@ -201,6 +256,7 @@ impl Height {
 }
 
 #[derive(Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Piece {
     pub color: Color,
     pub position: Position,
@@ -217,13 +273,16 @@ impl Piece {
     pub fn boom(&mut self) {
         self.height = self.height.boom()
     }
+    // Whether other is in range along a straight line or diagonal. This ignores anything in the
+    // way, so use Board::attackers_of to check if a boom is actually possible.
     pub fn can_boom(&self, other: &Piece) -> bool {
-        let dx = other.position.x - self.position.x;
-        let dy = other.position.y - self.position.y;
-        let height = i8::from(&self.height);
-        (dx <= height && (dy == 0 || dy == dx)) || (dy <= height && dx == 0)
+        let dx = (other.position.x - self.position.x).abs();
+        let dy = (other.position.y - self.position.y).abs();
+        let distance = dx.max(dy);
+        let in_line = dx == 0 || dy == 0 || dx == dy;
+        in_line && distance > 0 && distance <= i8::from(&self.height)
     }
-    // There are not enough bits to serialize the color :(
+    // There are not enough bits to serialize the color :( Board::pack stores it separately
     pub fn as_u8(&self) -> u8 {
         (u8::from(&self.height) << 6) + u8::from(self.position)
     }
@@ -239,6 +298,8 @@ impl Piece {
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Move {
     Boom(usize),
     Zoom(usize, Position),
@@ -248,36 +309,28 @@ pub enum Move {
 
 #[derive(Clone, Hash)]
 pub struct Board {
-    pub pieces: [Piece; 8],
+    // Only the first rules.piece_count() pieces are used, the rest are always dead
+    pub pieces: [Piece; MAX_PIECES],
     pub black_score: u8,
     pub white_score: u8,
     pub victory_by_concession: Option<Color>,
+    pub rules: RuleSet,
+    // Counters for the draw rules. These aren't part of the zobrist key.
+    pub plies_played: u16,
+    pub plies_since_progress: u16,
+    zobrist: u64,
 }
 impl Default for Board {
     fn default() -> Board {
-        Board {
-            pieces: [
-                Piece::new(Color::White, (2, 0)),
-                Piece::new(Color::White, (3, 0)),
-                Piece::new(Color::White, (4, 0)),
-                Piece::new(Color::White, (5, 0)),
-                Piece::new(Color::Black, (2, 7)),
-                Piece::new(Color::Black, (3, 7)),
-                Piece::new(Color::Black, (4, 7)),
-                Piece::new(Color::Black, (5, 7)),
-            ],
-            black_score: 0,
-            white_score: 0,
-            victory_by_concession: None,
-        }
+        Board::new(RuleSet::STANDARD)
     }
 }
 impl std::fmt::Debug for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "White: {}  Black {}", self.white_score, self.black_score)?;
-        for y in (0..8).rev() {
+        for y in (0..self.rules.height).rev() {
             write!(f, "{}| ", y + 1)?;
-            for x in 0..8 {
+            for x in 0..self.rules.width {
                 if let Some(index) = self.get_piece_at(&(x, y).into()) {
                     let piece = &self.pieces[index];
                     if piece.height == Height::Dead {
@@ -294,71 +347,92 @@ impl std::fmt::Debug for Board {
             }
             writeln!(f)?;
         }
-        writeln!(f, " +-----------------")?;
-        writeln!(f, "   a b c d e f g h")?;
+        let width = self.rules.width as usize;
+        writeln!(f, " +-{}", "-".repeat(2 * width))?;
+        writeln!(f, "   {}", &"a b c d e f g h"[..2 * width - 1])?;
         Ok(())
     }
 }
 impl Board {
-    pub fn apply_move(&self, mov: &Move) -> Board {
-        let mut new_board = self.clone();
-        match mov {
-            Move::Boom(idx) => {
-                new_board.pieces[*idx].boom();
-            }
-            Move::Zoom(idx, position) => {
-                new_board.pieces[*idx].position = *position;
-            }
-            Move::Score(idx) => {
-                let points_scored = Into::<u8>::into(&new_board.pieces[*idx].height);
-                match new_board.pieces[*idx].color {
-                    Color::White => new_board.white_score += points_scored,
-                    Color::Black => new_board.black_score += points_scored,
-                }
-                new_board.pieces[*idx].height = Height::Dead;
-            }
-            Move::Concede(color) => {
-                new_board.victory_by_concession = Some(color.invert());
+    // The starting position for the given rules
+    pub fn new(rules: RuleSet) -> Board {
+        let mut board = Board::empty(rules);
+        for color in [Color::White, Color::Black] {
+            let y = rules.back_rank(&color);
+            for (slot, index) in rules.pieces(&color).enumerate() {
+                board.pieces[index] = Piece {
+                    color,
+                    position: (rules.start_files[slot], y).into(),
+                    height: rules.start_heights[slot],
+                };
             }
         }
+        board.rehash(&Color::White);
+        board
+    }
+    // A board with no living pieces, for filling in piece by piece. Remember to rehash afterwards.
+    pub(crate) fn empty(rules: RuleSet) -> Board {
+        Board {
+            pieces: std::array::from_fn(|index| Piece {
+                color: rules.color_of(index),
+                position: (0, 0).into(),
+                height: Height::Dead,
+            }),
+            black_score: 0,
+            white_score: 0,
+            victory_by_concession: None,
+            rules,
+            plies_played: 0,
+            plies_since_progress: 0,
+            zobrist: 0,
+        }
+    }
+    pub fn apply_move(&self, mov: &Move) -> Board {
+        let mut new_board = self.clone();
+        new_board.make_move(mov);
         new_board
     }
     pub fn get_piece(&self, index: usize) -> &Piece {
         &self.pieces[index]
     }
     pub fn get_piece_at(&self, position: &Position) -> Option<usize> {
-        (0..8).find(|&i| {
+        (0..self.rules.piece_count()).find(|&i| {
             self.pieces[i].height != Height::Dead && self.pieces[i].position.index == position.index
         })
     }
-    pub fn legal_moves(&self, color: &Color) -> LegalMoveIterator {
+    pub fn legal_moves(&self, color: &Color) -> LegalMoveIterator<'_> {
         LegalMoveIterator::for_color(self, color)
     }
-    pub fn legal_moves_for(&self, piece: &Piece) -> LegalMoveIterator {
+    pub fn legal_moves_for(&self, piece: &Piece) -> LegalMoveIterator<'_> {
         let piece_index = self
             .get_piece_at(&piece.position)
             .expect("piece needs to be on the board");
         LegalMoveIterator::for_piece(self, piece_index)
     }
     pub fn boomable(&self, piece_index: usize) -> bool {
-        assert!(piece_index < 8);
-        let piece = &self.pieces[piece_index];
-        // White
-        if piece_index < 4 {
-            self.pieces[4..].iter().any(|other| other.can_boom(piece))
-        } else {
-            self.pieces[..4].iter().any(|other| other.can_boom(piece))
-        }
+        assert!(piece_index < self.rules.piece_count());
+        self.attackers_of(piece_index).next().is_some()
     }
     pub fn winner(&self) -> Option<Winner> {
+        self.scored_winner()
+            .or_else(|| self.draw_limit().map(|_| Winner::Draw))
+    }
+    // The winner, ignoring the draw rules
+    fn scored_winner(&self) -> Option<Winner> {
         if let Some(color) = self.victory_by_concession {
             return Some(match color {
                 Color::White => Winner::White,
                 Color::Black => Winner::Black,
             });
         }
-        let white_potential: u8 = self.pieces[0..4].iter().map(|p| u8::from(&p.height)).sum();
-        let black_potential: u8 = self.pieces[4..8].iter().map(|p| u8::from(&p.height)).sum();
+        let potential = |color| -> u8 {
+            self.pieces[self.rules.pieces(&color)]
+                .iter()
+                .map(|p| u8::from(&p.height))
+                .sum()
+        };
+        let white_potential = potential(Color::White);
+        let black_potential = potential(Color::Black);
         if white_potential == 0 || black_potential == 0 {
             Some(match self.white_score.cmp(&self.black_score) {
                 std::cmp::Ordering::Less => Winner::Black,
@@ -373,7 +447,10 @@ impl Board {
             None
         }
     }
+    // Compression only supports the standard board, and doesn't include the draw counters. Use
+    // Board::pack to encode everything.
     pub fn compress(&self) -> (u64, u8, u8) {
+        debug_assert!(self.rules.has_standard_board());
         let pieces = ((self.pieces[7].as_u8() as u64) << 56)
             + ((self.pieces[6].as_u8() as u64) << 48)
             + ((self.pieces[5].as_u8() as u64) << 40)
@@ -385,25 +462,31 @@ impl Board {
         (pieces, self.white_score, self.black_score)
     }
     pub fn decompress(pieces: u64, white_score: u8, black_score: u8) -> Board {
-        let pieces = [
-            Piece::from_u8(Color::White, (pieces & 0xff) as u8),
-            Piece::from_u8(Color::White, ((pieces >> 8) & 0xff) as u8),
-            Piece::from_u8(Color::White, ((pieces >> 16) & 0xff) as u8),
-            Piece::from_u8(Color::White, ((pieces >> 24) & 0xff) as u8),
-            Piece::from_u8(Color::Black, ((pieces >> 32) & 0xff) as u8),
-            Piece::from_u8(Color::Black, ((pieces >> 40) & 0xff) as u8),
-            Piece::from_u8(Color::Black, ((pieces >> 48) & 0xff) as u8),
-            Piece::from_u8(Color::Black, ((pieces >> 56) & 0xff) as u8),
-        ];
-        Board {
-            pieces,
-            white_score,
-            black_score,
-            victory_by_concession: None,
+        let rules = RuleSet::STANDARD;
+        let mut board = Board::empty(rules);
+        for index in 0..rules.piece_count() {
+            let u = ((pieces >> (8 * index)) & 0xff) as u8;
+            board.pieces[index] = Piece::from_u8(rules.color_of(index), u);
         }
+        board.white_score = white_score;
+        board.black_score = black_score;
+        board.rehash(&Color::White);
+        board
     }
 }
 
+// The order that pieces are moved in, starting from the left and going counterclockwise
+pub(crate) const DIRECTIONS: [(i8, i8); 8] = [
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+];
+
 pub struct LegalMoveIterator<'a> {
     board: &'a Board,
     piece_index: usize,
@@ -426,29 +509,20 @@ impl<'a> LegalMoveIterator<'a> {
         iter
     }
     pub fn for_color(board: &'a Board, color: &Color) -> LegalMoveIterator<'a> {
-        let mut iter = match color {
-            Color::White => LegalMoveIterator {
-                board,
-                piece_index: 0,
-                max_piece_index: 3,
-                dir_index: 0,
-                distance: 0,
-                has_scored: false,
-            },
-            Color::Black => LegalMoveIterator {
-                board,
-                piece_index: 4,
-                max_piece_index: 7,
-                dir_index: 0,
-                distance: 0,
-                has_scored: false,
-            },
+        let range = board.rules.pieces(color);
+        let mut iter = LegalMoveIterator {
+            board,
+            piece_index: range.start,
+            max_piece_index: range.end - 1,
+            dir_index: 0,
+            distance: 0,
+            has_scored: false,
         };
         iter.advance();
         iter
     }
     fn advance(&mut self) {
-        if self.piece_index < 8 {
+        if self.piece_index <= self.max_piece_index {
             let piece = &self.board.pieces[self.piece_index];
             if self.distance == i8::from(&piece.height) {
                 self.end_of_the_line();
@@ -463,6 +537,7 @@ impl<'a> LegalMoveIterator<'a> {
         if self.dir_index >= 8 {
             self.dir_index = 0;
             self.piece_index += 1;
+            self.has_scored = false;
         }
         self.advance();
     }
@@ -476,20 +551,11 @@ impl<'a> Iterator for LegalMoveIterator<'a> {
             return None;
         }
         let piece = &self.board.pieces[piece_index];
-        let (dx, dy) = [
-            (-1, 0),
-            (-1, -1),
-            (0, -1),
-            (1, -1),
-            (1, 0),
-            (1, 1),
-            (0, 1),
-            (-1, 1),
-        ][self.dir_index];
+        let (dx, dy) = DIRECTIONS[self.dir_index];
         let (dx, dy) = (dx * self.distance, dy * self.distance);
         // Attempt movement in every direction
         // Set our initial range and position
-        let position = piece.position.offset(dx, dy);
+        let position = piece.position.offset(dx, dy, &self.board.rules);
         match &position {
             PositionOffset::Valid(pos) => {
                 if let Some(piece_at_pos_index) = self.board.get_piece_at(pos) {
@@ -505,11 +571,13 @@ impl<'a> Iterator for LegalMoveIterator<'a> {
                 }
             }
             PositionOffset::ScoreZone(color) => {
+                // We only want to have scoring as an option once, even if
+                // it's possible to score in multiple different ways
+                let can_score = !self.has_scored && color == &piece.color;
+                self.has_scored |= can_score;
+                // This resets has_scored if we move on to the next piece
                 self.end_of_the_line();
-                if !self.has_scored && color == &piece.color {
-                    // We only want to have scoring as an option once, even if
-                    // it's possible to score in multiple different ways
-                    self.has_scored = true;
+                if can_score {
                     return Some(Move::Score(piece_index));
                 }
                 self.next()
@@ -522,15 +590,27 @@ impl<'a> Iterator for LegalMoveIterator<'a> {
     }
 }
 
+// Only decide is required. The rest let players keep state between moves, and do nothing by default.
 pub trait GamePlayer {
     fn decide(&mut self, board: &Board, color: &Color) -> Move;
+    // A game is starting from board, with this player playing color
+    fn new_game(&mut self, _board: &Board, _color: &Color) {}
+    // The opponent played mov, leaving board
+    fn opponent_moved(&mut self, _mov: &Move, _board: &Board) {}
+    fn game_over(&mut self, _winner: &Winner) {}
+    // A hint for how long each decision should take. Players are free to ignore it.
+    fn set_time_budget(&mut self, _budget: Duration) {}
 }
 
 pub struct Game<W: GamePlayer, B: GamePlayer> {
-    board: Board,
     white_player: W,
     black_player: B,
-    turn: Color,
+    // The board and side to move at every ply, starting with the initial position
+    positions: Vec<(Board, Color)>,
+    // The moves played so far, including any that have been undone but could be redone
+    moves: Vec<Move>,
+    ply: usize,
+    observers: Vec<Box<dyn GameObserver>>,
 }
 impl<W, B> Game<W, B>
 where
@@ -538,41 +618,158 @@ where
     B: GamePlayer,
 {
     pub fn new(white_player: W, black_player: B) -> Self {
+        Self::from_position(white_player, black_player, Board::default(), Color::White)
+    }
+    pub fn from_position(
+        mut white_player: W,
+        mut black_player: B,
+        board: Board,
+        turn: Color,
+    ) -> Self {
+        white_player.new_game(&board, &Color::White);
+        black_player.new_game(&board, &Color::Black);
         Self {
-            board: Board::default(),
             white_player,
             black_player,
-            turn: Color::White,
+            positions: vec![(board, turn)],
+            moves: vec![],
+            ply: 0,
+            observers: vec![],
         }
     }
+    pub fn add_observer(&mut self, observer: impl GameObserver + 'static) {
+        self.observers.push(Box::new(observer));
+    }
     pub fn play_turn(&mut self) -> Option<Winner> {
-        let mov = match self.turn {
-            Color::White => self.white_player.decide(&self.board, &self.turn),
-            Color::Black => self.black_player.decide(&self.board, &self.turn),
+        let (board, turn) = &self.positions[self.ply];
+        let mov = match turn {
+            Color::White => self.white_player.decide(board, turn),
+            Color::Black => self.black_player.decide(board, turn),
         };
         self.apply_move(&mov);
         self.winner()
     }
     pub fn apply_move(&mut self, mov: &Move) {
-        self.board = self.board.apply_move(mov);
-        self.turn = self.turn.invert();
+        // Playing a new move discards any moves that could have been redone
+        self.positions.truncate(self.ply + 1);
+        self.moves.truncate(self.ply);
+        let (board, turn) = &self.positions[self.ply];
+        let turn = *turn;
+        let next = (board.apply_move(mov), turn.invert());
+        self.positions.push(next);
+        self.moves.push(*mov);
+        self.ply += 1;
+        let winner = self.winner();
+        let (board, _) = &self.positions[self.ply];
+        match turn {
+            Color::White => self.black_player.opponent_moved(mov, board),
+            Color::Black => self.white_player.opponent_moved(mov, board),
+        }
+        if let Some(winner) = &winner {
+            self.white_player.game_over(winner);
+            self.black_player.game_over(winner);
+        }
+        if !self.observers.is_empty() {
+            let (before, turn) = &self.positions[self.ply - 1];
+            let (after, _) = &self.positions[self.ply];
+            for observer in self.observers.iter_mut() {
+                observer::notify(observer.as_mut(), mov, turn, before, after, winner.as_ref());
+            }
+        }
+    }
+    pub fn white_player_mut(&mut self) -> &mut W {
+        &mut self.white_player
+    }
+    pub fn black_player_mut(&mut self) -> &mut B {
+        &mut self.black_player
+    }
+    pub fn set_time_budget(&mut self, color: &Color, budget: Duration) {
+        match color {
+            Color::White => self.white_player.set_time_budget(budget),
+            Color::Black => self.black_player.set_time_budget(budget),
+        }
+    }
+    pub fn try_apply_move(&mut self, mov: &Move) -> Result<(), MoveError> {
+        self.board().validate_move(mov, self.turn())?;
+        self.apply_move(mov);
+        Ok(())
     }
     pub fn finish_game(&mut self) -> Winner {
-        while self.board.winner().is_none() {
+        while self.winner().is_none() {
             self.play_turn();
         }
         self.winner().expect("there must be a winner")
     }
     pub fn winner(&self) -> Option<Winner> {
-        self.board.winner()
+        self.board()
+            .winner()
+            .or_else(|| self.is_repetition().then_some(Winner::Draw))
+    }
+
+    pub fn draw_reason(&self) -> Option<DrawReason> {
+        if self.board().winner().is_none() && self.is_repetition() {
+            Some(DrawReason::Repetition)
+        } else {
+            self.board().draw_reason()
+        }
+    }
+
+    fn is_repetition(&self) -> bool {
+        let earlier = self.positions[..self.ply].iter().rev();
+        self.board()
+            .is_repetition(earlier.map(|(board, _)| board.zobrist()))
     }
 
     pub fn board(&self) -> &Board {
-        &self.board
+        &self.positions[self.ply].0
     }
 
     pub fn turn(&self) -> &Color {
-        &self.turn
+        &self.positions[self.ply].1
+    }
+
+    // The moves that led to the current position
+    pub fn history(&self) -> &[Move] {
+        &self.moves[..self.ply]
+    }
+
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    // Takes back the last move, returning it
+    pub fn undo(&mut self) -> Option<Move> {
+        if self.ply == 0 {
+            return None;
+        }
+        self.ply -= 1;
+        self.restart_players();
+        Some(self.moves[self.ply])
+    }
+
+    // Replays the last move that was undone, returning it
+    pub fn redo(&mut self) -> Option<Move> {
+        if self.ply == self.moves.len() {
+            return None;
+        }
+        self.ply += 1;
+        self.restart_players();
+        Some(self.moves[self.ply - 1])
+    }
+
+    // Jumps to any ply that has been played, including undone plies that could be redone
+    pub fn goto_ply(&mut self, ply: usize) {
+        assert!(ply <= self.moves.len());
+        self.ply = ply;
+        self.restart_players();
+    }
+
+    // Players only follow the game forwards, so after jumping around they are told that a new game
+    // is starting from wherever the game is now
+    fn restart_players(&mut self) {
+        let (board, _) = &self.positions[self.ply];
+        self.white_player.new_game(board, &Color::White);
+        self.black_player.new_game(board, &Color::Black);
     }
 }
 
@@ -742,6 +939,132 @@ mod tests {
             vec![]
         );
     }
+    struct NoPlayer();
+    impl GamePlayer for NoPlayer {
+        fn decide(&mut self, _board: &Board, _color: &Color) -> Move {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut game = Game::new(NoPlayer(), NoPlayer());
+        let moves = [zoom!(0, c4), zoom!(4, c5), boom!(4), zoom!(5, d5)];
+        let mut boards = vec![game.board().compress()];
+        for mov in moves.iter() {
+            game.apply_move(mov);
+            boards.push(game.board().compress());
+        }
+        assert_eq!(game.history(), &moves);
+        assert_eq!(game.undo(), Some(zoom!(5, d5)));
+        assert_eq!(game.undo(), Some(boom!(4)));
+        assert_eq!(game.history(), &moves[..2]);
+        assert_eq!(game.board().compress(), boards[2]);
+        assert_eq!(game.turn(), &Color::White);
+        assert_eq!(game.redo(), Some(boom!(4)));
+        assert_eq!(game.board().compress(), boards[3]);
+        assert_eq!(game.turn(), &Color::Black);
+        game.goto_ply(0);
+        assert_eq!(game.undo(), None);
+        assert_eq!(game.board().compress(), boards[0]);
+        game.goto_ply(4);
+        assert_eq!(game.redo(), None);
+        assert_eq!(game.board().compress(), boards[4]);
+        assert_eq!(game.turn(), &Color::White);
+        // Playing a new move after undoing forgets the undone moves
+        game.goto_ply(1);
+        game.apply_move(&zoom!(6, e5));
+        assert_eq!(game.history(), &[zoom!(0, c4), zoom!(6, e5)]);
+        assert_eq!(game.redo(), None);
+    }
+
+    // Plays the moves it's given in order, and keeps a log of everything it's told
+    struct LoggingPlayer {
+        moves: Vec<Move>,
+        log: std::rc::Rc<std::cell::RefCell<Vec<String>>>,
+    }
+    impl GamePlayer for LoggingPlayer {
+        fn decide(&mut self, _board: &Board, color: &Color) -> Move {
+            self.log.borrow_mut().push(format!("{color:?} decides"));
+            self.moves.remove(0)
+        }
+        fn new_game(&mut self, board: &Board, color: &Color) {
+            let fen = board.to_fen(color);
+            self.log
+                .borrow_mut()
+                .push(format!("{color:?} new game {fen}"));
+        }
+        fn opponent_moved(&mut self, mov: &Move, board: &Board) {
+            assert_eq!(board.plies_played, 1);
+            self.log.borrow_mut().push(format!("opponent {mov:?}"));
+        }
+        fn game_over(&mut self, winner: &Winner) {
+            self.log.borrow_mut().push(format!("{winner:?} won"));
+        }
+        fn set_time_budget(&mut self, budget: Duration) {
+            self.log.borrow_mut().push(format!("budget {budget:?}"));
+        }
+    }
+
+    #[test]
+    fn game_player_lifecycle() {
+        let log = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+        let white = LoggingPlayer {
+            moves: vec![Move::Concede(Color::White)],
+            log: log.clone(),
+        };
+        let black = LoggingPlayer {
+            moves: vec![],
+            log: log.clone(),
+        };
+        let (board, turn) = Board::from_fen("8/8/8/8/8/8/8/w16b1 w 0 0 -").unwrap();
+        let mut game = Game::from_position(white, black, board, turn);
+        game.set_time_budget(&Color::Black, Duration::from_secs(1));
+        assert_eq!(game.play_turn(), Some(Winner::Black));
+        game.undo();
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                "White new game 8/8/8/8/8/8/8/w16b1 w 0 0 - 0 0",
+                "Black new game 8/8/8/8/8/8/8/w16b1 b 0 0 - 0 0",
+                "budget 1s",
+                "White decides",
+                "opponent Concede(White)",
+                "Black won",
+                "Black won",
+                "White new game 8/8/8/8/8/8/8/w16b1 w 0 0 - 0 0",
+                "Black new game 8/8/8/8/8/8/8/w16b1 b 0 0 - 0 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn game_rejects_moves_out_of_turn() {
+        let mut game = Game::new(NoPlayer(), NoPlayer());
+        assert_eq!(
+            game.try_apply_move(&zoom!(4, c7)),
+            Err(MoveError::WrongSide)
+        );
+        assert_eq!(game.try_apply_move(&zoom!(0, c2)), Ok(()));
+        assert_eq!(game.try_apply_move(&zoom!(4, c7)), Ok(()));
+        assert_eq!(game.history(), &[zoom!(0, c2), zoom!(4, c7)]);
+    }
+
+    #[test]
+    fn game_draws_by_repetition() {
+        let mut game = Game::new(NoPlayer(), NoPlayer());
+        let shuffle = [zoom!(0, c2), zoom!(4, c7), zoom!(0, c1), zoom!(4, c8)];
+        for mov in shuffle.iter().chain(shuffle.iter()) {
+            assert_eq!(game.winner(), None);
+            game.apply_move(mov);
+        }
+        assert_eq!(game.winner(), Some(Winner::Draw));
+        assert_eq!(game.draw_reason(), Some(DrawReason::Repetition));
+        game.undo();
+        assert_eq!(game.winner(), None);
+        assert_eq!(game.draw_reason(), None);
+    }
+
     #[test]
     fn fuzz_board_compression() {
         let mut rand = thread_rng();
@@ -0,0 +1,123 @@
+use crate::{Color, Move, Position, MAX_PIECES};
+
+/*
+ * A 16 bit encoding of moves, for move lists, transposition tables and game databases.
+ *
+ * Bits are numbered from the least significant bit:
+ *
+ *   0-5    The square a piece zooms to, as Position::index. For a concession, bit 0 is set if
+ *          Black conceded.
+ *   6-9    The index of the piece that zooms, scores or is boomed
+ *   10-13  Unused, always 0
+ *   14-15  The kind of move: 0 for zoom, 1 for boom, 2 for score and 3 for concede
+ *
+ * Any bits a kind of move doesn't use are 0, so every move has exactly one encoding. Converting a
+ * move with a piece index of MAX_PIECES or more panics, since no board has that piece.
+ */
+
+const ZOOM: u16 = 0;
+const BOOM: u16 = 1;
+const SCORE: u16 = 2;
+const CONCEDE: u16 = 3;
+
+fn encode_piece(index: usize) -> u16 {
+    assert!(index < MAX_PIECES, "piece index {index} can't be encoded");
+    (index as u16) << 6
+}
+
+impl From<Move> for u16 {
+    fn from(mov: Move) -> Self {
+        match mov {
+            Move::Zoom(index, position) => {
+                (ZOOM << 14) | encode_piece(index) | u8::from(position) as u16
+            }
+            Move::Boom(index) => (BOOM << 14) | encode_piece(index),
+            Move::Score(index) => (SCORE << 14) | encode_piece(index),
+            Move::Concede(Color::White) => CONCEDE << 14,
+            Move::Concede(Color::Black) => (CONCEDE << 14) | 1,
+        }
+    }
+}
+
+impl TryFrom<u16> for Move {
+    type Error = ();
+    fn try_from(encoded: u16) -> Result<Self, Self::Error> {
+        let square = encoded & 0b111111;
+        let index = ((encoded >> 6) & 0b1111) as usize;
+        if encoded & (0b1111 << 10) != 0 {
+            return Err(());
+        }
+        match encoded >> 14 {
+            ZOOM => Ok(Move::Zoom(index, Position::from(square as u8))),
+            BOOM if square == 0 => Ok(Move::Boom(index)),
+            SCORE if square == 0 => Ok(Move::Score(index)),
+            CONCEDE if index == 0 && square == 0 => Ok(Move::Concede(Color::White)),
+            CONCEDE if index == 0 && square == 1 => Ok(Move::Concede(Color::Black)),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Board;
+    use rand::prelude::*;
+
+    #[test]
+    fn round_trips() {
+        let mut moves = vec![Move::Concede(Color::White), Move::Concede(Color::Black)];
+        for index in 0..MAX_PIECES {
+            moves.push(Move::Boom(index));
+            moves.push(Move::Score(index));
+            for square in 0..64u8 {
+                moves.push(Move::Zoom(index, Position::from(square)));
+            }
+        }
+        let mut encodings = vec![];
+        for mov in moves {
+            let encoded = u16::from(mov);
+            assert_eq!(Move::try_from(encoded), Ok(mov));
+            encodings.push(encoded);
+        }
+        let count = encodings.len();
+        encodings.sort();
+        encodings.dedup();
+        assert_eq!(encodings.len(), count);
+    }
+
+    #[test]
+    fn round_trips_random_games() {
+        let mut rng = thread_rng();
+        for _ in 0..10 {
+            let mut board = Board::default();
+            let mut turn = Color::White;
+            while board.winner().is_none() {
+                for mov in board.legal_moves(&turn) {
+                    assert_eq!(Move::try_from(u16::from(mov)), Ok(mov));
+                }
+                let mov = board.legal_moves(&turn).choose(&mut rng).unwrap();
+                board.make_move(&mov);
+                turn = turn.invert();
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_encodings() {
+        let c4: Position = "c4".try_into().unwrap();
+        let zoom = u16::from(Move::Zoom(3, c4));
+        assert_eq!(zoom, (3 << 6) | 26);
+        assert_eq!(Move::try_from(zoom | 1 << 10), Err(()));
+        assert_eq!(Move::try_from(u16::from(Move::Boom(3)) | 1), Err(()));
+        assert_eq!(Move::try_from(u16::from(Move::Score(3)) | 1), Err(()));
+        assert_eq!(Move::try_from(0b11 << 14 | 2), Err(()));
+        assert_eq!(Move::try_from(0b11 << 14 | 1 << 6), Err(()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn panics_on_impossible_pieces() {
+        let _ = u16::from(Move::Boom(MAX_PIECES));
+    }
+}
@@ -0,0 +1,145 @@
+use std::ops::Deref;
+
+use crate::{Board, Color, Move, MAX_PIECES_PER_SIDE};
+
+/*
+ * A fixed capacity list of moves, so that move generation doesn't have to allocate.
+ *
+ * A piece can make at most 3 moves in each of the 8 directions, plus a score, so a side can never
+ * have more than MoveList::CAPACITY legal moves. Pushing more than that panics.
+ *
+ *   let mut moves = MoveList::new();
+ *   board.generate_moves(&color, &mut moves);
+ *   for mov in &moves { ... }
+ */
+
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MoveList::CAPACITY],
+    len: usize,
+}
+impl MoveList {
+    pub const CAPACITY: usize = MAX_PIECES_PER_SIDE * (8 * 3 + 1);
+
+    pub fn new() -> MoveList {
+        MoveList {
+            // Never read, it's just filler
+            moves: [Move::Concede(Color::White); MoveList::CAPACITY],
+            len: 0,
+        }
+    }
+    pub fn push(&mut self, mov: Move) {
+        assert!(self.len < MoveList::CAPACITY, "the move list is full");
+        self.moves[self.len] = mov;
+        self.len += 1;
+    }
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+    pub fn as_slice(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+    pub fn as_mut_slice(&mut self) -> &mut [Move] {
+        &mut self.moves[..self.len]
+    }
+}
+impl Default for MoveList {
+    fn default() -> MoveList {
+        MoveList::new()
+    }
+}
+impl Deref for MoveList {
+    type Target = [Move];
+    fn deref(&self) -> &[Move] {
+        self.as_slice()
+    }
+}
+impl std::fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+impl PartialEq for MoveList {
+    fn eq(&self, other: &MoveList) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+impl Eq for MoveList {}
+impl Extend<Move> for MoveList {
+    fn extend<I: IntoIterator<Item = Move>>(&mut self, iter: I) {
+        for mov in iter {
+            self.push(mov);
+        }
+    }
+}
+impl FromIterator<Move> for MoveList {
+    fn from_iter<I: IntoIterator<Item = Move>>(iter: I) -> MoveList {
+        let mut moves = MoveList::new();
+        moves.extend(iter);
+        moves
+    }
+}
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+
+impl Board {
+    // The same moves as Board::legal_moves, in the same order
+    pub fn legal_move_list(&self, color: &Color) -> MoveList {
+        let mut moves = MoveList::new();
+        self.generate_moves(color, &mut moves);
+        moves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Height, RuleSet};
+    use rand::prelude::*;
+
+    #[test]
+    fn matches_legal_moves() {
+        let mut rng = thread_rng();
+        for rules in [RuleSet::STANDARD, RuleSet::new(6, 5, 3)] {
+            for _ in 0..10 {
+                let mut board = Board::new(rules);
+                let mut turn = Color::White;
+                while board.winner().is_none() {
+                    let moves = board.legal_move_list(&turn);
+                    assert!(moves
+                        .iter()
+                        .eq(board.legal_moves(&turn).collect::<Vec<_>>().iter()));
+                    let mov = *moves.choose(&mut rng).unwrap();
+                    board.make_move(&mov);
+                    turn = turn.invert();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn holds_the_most_moves_possible() {
+        // Eight towers in the middle of the board
+        let rules = RuleSet::new(8, 8, 8).with_start_heights(&[Height::Three; 8]);
+        let mut board = Board::new(rules);
+        for (index, file) in (0..8).enumerate() {
+            board.pieces[index].position = (file, 3 + (file % 2)).into();
+        }
+        let moves = board.legal_move_list(&Color::White);
+        assert!(moves.len() <= MoveList::CAPACITY);
+        assert_eq!(moves.len(), board.legal_moves(&Color::White).count());
+
+        let mut list = MoveList::new();
+        assert!(list.is_empty());
+        list.push(Move::Boom(1));
+        list.extend([Move::Score(2)]);
+        assert_eq!(list.as_slice(), &[Move::Boom(1), Move::Score(2)]);
+        list.clear();
+        assert_eq!(list, MoveList::default());
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{Board, Color, Height, LegalMoveIterator, Move, Position};
+
+/*
+ * Algebraic move notation.
+ *
+ * Zoom:    c1-c4    the piece on c1 moves to c4
+ * Boom:    c1xd3    the piece on c1 booms the piece on d3
+ * Score:   c1^      the piece on c1 moves into the score zone
+ * Concede: concede-w / concede-b
+ *
+ * A boom only records the piece being boomed, so the booming square is recovered from the board.
+ * If no piece can legally perform the boom, it is written without a source square (xd3).
+ */
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NotationError {
+    Malformed(String),
+    NoPieceAt(Position),
+    IllegalMove(String),
+}
+impl std::fmt::Display for NotationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotationError::Malformed(notation) => write!(f, "Malformed move \"{notation}\""),
+            NotationError::NoPieceAt(position) => write!(f, "There is no piece on {position}"),
+            NotationError::IllegalMove(notation) => write!(f, "Illegal move \"{notation}\""),
+        }
+    }
+}
+impl std::error::Error for NotationError {}
+
+impl Move {
+    pub fn to_notation(&self, board: &Board) -> String {
+        match self {
+            Move::Zoom(index, position) => {
+                format!("{}-{}", board.pieces[*index].position, position)
+            }
+            Move::Boom(index) => {
+                let target = board.pieces[*index].position;
+                match Self::find_boomer(board, *index) {
+                    Some(boomer) => format!("{}x{}", board.pieces[boomer].position, target),
+                    None => format!("x{target}"),
+                }
+            }
+            Move::Score(index) => format!("{}^", board.pieces[*index].position),
+            Move::Concede(Color::White) => "concede-w".to_string(),
+            Move::Concede(Color::Black) => "concede-b".to_string(),
+        }
+    }
+
+    pub fn from_notation(notation: &str, board: &Board) -> Result<Move, NotationError> {
+        let notation = notation.trim();
+        let malformed = || NotationError::Malformed(notation.to_string());
+        let illegal = || NotationError::IllegalMove(notation.to_string());
+        let square = |s: &str| Position::try_from(s).map_err(|_| malformed());
+        let piece_at = |position: Position| {
+            board
+                .get_piece_at(&position)
+                .ok_or(NotationError::NoPieceAt(position))
+        };
+        if let Some(color) = notation.strip_prefix("concede-") {
+            return color
+                .parse::<Color>()
+                .map(Move::Concede)
+                .map_err(|_| malformed());
+        }
+        let (source, mov) = if let Some(from) = notation.strip_suffix('^') {
+            let index = piece_at(square(from)?)?;
+            (Some(index), Move::Score(index))
+        } else if let Some((from, to)) = notation.split_once('-') {
+            let index = piece_at(square(from)?)?;
+            (Some(index), Move::Zoom(index, square(to)?))
+        } else if let Some((from, to)) = notation.split_once('x') {
+            let target = piece_at(square(to)?)?;
+            if from.is_empty() {
+                (Self::find_boomer(board, target), Move::Boom(target))
+            } else {
+                (Some(piece_at(square(from)?)?), Move::Boom(target))
+            }
+        } else {
+            return Err(malformed());
+        };
+        let source = source.ok_or_else(illegal)?;
+        if LegalMoveIterator::for_piece(board, source).any(|m| m == mov) {
+            Ok(mov)
+        } else {
+            Err(illegal())
+        }
+    }
+
+    // The first opposing piece that is able to boom the given piece
+    fn find_boomer(board: &Board, target: usize) -> Option<usize> {
+        let color = board.pieces[target].color;
+        board
+            .rules
+            .pieces(&color.invert())
+            .filter(|&i| board.pieces[i].height != Height::Dead)
+            .find(|&i| LegalMoveIterator::for_piece(board, i).any(|m| m == Move::Boom(target)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(square: &str) -> Position {
+        square.try_into().unwrap()
+    }
+
+    #[test]
+    fn notation_round_trips_legal_moves() {
+        let mut board = Board::default();
+        board.pieces[0].position = position("c6");
+        board.pieces[1].position = position("d5");
+        board.pieces[4].position = position("c7");
+        for color in [Color::White, Color::Black] {
+            for mov in board.legal_moves(&color) {
+                let notation = mov.to_notation(&board);
+                assert_eq!(
+                    Move::from_notation(&notation, &board),
+                    Ok(mov),
+                    "{notation}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn notation_examples() {
+        let mut board = Board::default();
+        board.pieces[4].position = position("e3");
+        assert_eq!(Move::Zoom(0, position("c4")).to_notation(&board), "c1-c4");
+        assert_eq!(Move::Boom(4).to_notation(&board), "c1xe3");
+        assert_eq!(Move::Concede(Color::Black).to_notation(&board), "concede-b");
+        assert_eq!(Move::from_notation("xe3", &board), Ok(Move::Boom(4)));
+        assert_eq!(
+            Move::from_notation("concede-w", &board),
+            Ok(Move::Concede(Color::White))
+        );
+        board.pieces[0].position = position("c8");
+        assert_eq!(Move::Score(0).to_notation(&board), "c8^");
+        assert_eq!(Move::from_notation("c8^", &board), Ok(Move::Score(0)));
+    }
+
+    #[test]
+    fn notation_errors() {
+        let board = Board::default();
+        assert_eq!(
+            Move::from_notation("c1c4", &board),
+            Err(NotationError::Malformed("c1c4".to_string()))
+        );
+        assert_eq!(
+            Move::from_notation("c1-c9", &board),
+            Err(NotationError::Malformed("c1-c9".to_string()))
+        );
+        assert_eq!(
+            Move::from_notation("a1-a2", &board),
+            Err(NotationError::NoPieceAt(position("a1")))
+        );
+        assert_eq!(
+            Move::from_notation("c1-c5", &board),
+            Err(NotationError::IllegalMove("c1-c5".to_string()))
+        );
+        assert_eq!(
+            Move::from_notation("c1xc8", &board),
+            Err(NotationError::IllegalMove("c1xc8".to_string()))
+        );
+        assert_eq!(
+            Move::from_notation("c1^", &board),
+            Err(NotationError::IllegalMove("c1^".to_string()))
+        );
+    }
+}
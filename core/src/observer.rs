@@ -0,0 +1,122 @@
+use crate::{Board, Color, Height, Move, Winner};
+
+/*
+ * Hooks for anything that wants to know what happens in a Game, like recorders, animations and
+ * statistics collectors.
+ *
+ * Observers are registered with Game::add_observer, and are told about every move that Game plays,
+ * whether it was decided by a player or applied directly. Every callback gets the board from before
+ * the move and the board after it. For a single move the callbacks come in this order:
+ *
+ *   move_played, always
+ *   piece_boomed, then piece_killed if that was the boomed piece's last story
+ *   piece_scored
+ *   game_over, if the move ended the game
+ *
+ * Undoing, redoing and jumping to another ply don't play any moves, so observers aren't told.
+ */
+
+pub trait GameObserver {
+    fn move_played(&mut self, _mov: &Move, _color: &Color, _before: &Board, _after: &Board) {}
+    fn piece_boomed(&mut self, _piece_index: usize, _before: &Board, _after: &Board) {}
+    fn piece_killed(&mut self, _piece_index: usize, _before: &Board, _after: &Board) {}
+    fn piece_scored(&mut self, _piece_index: usize, _points: u8, _before: &Board, _after: &Board) {}
+    fn game_over(&mut self, _winner: &Winner, _before: &Board, _after: &Board) {}
+}
+
+// Calls the callbacks for a single move, in order
+pub(crate) fn notify(
+    observer: &mut dyn GameObserver,
+    mov: &Move,
+    color: &Color,
+    before: &Board,
+    after: &Board,
+    winner: Option<&Winner>,
+) {
+    observer.move_played(mov, color, before, after);
+    match mov {
+        Move::Boom(index) => {
+            observer.piece_boomed(*index, before, after);
+            if after.pieces[*index].height == Height::Dead {
+                observer.piece_killed(*index, before, after);
+            }
+        }
+        Move::Score(index) => {
+            let points = u8::from(&before.pieces[*index].height);
+            observer.piece_scored(*index, points, before, after);
+        }
+        Move::Zoom(_, _) | Move::Concede(_) => {}
+    }
+    if let Some(winner) = winner {
+        observer.game_over(winner, before, after);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Game, GamePlayer};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct Recorder(Rc<RefCell<Vec<String>>>);
+    impl GameObserver for Recorder {
+        fn move_played(&mut self, mov: &Move, color: &Color, before: &Board, after: &Board) {
+            assert_eq!(before.apply_move(mov).zobrist(), after.zobrist());
+            self.0.borrow_mut().push(format!("{color:?} {mov:?}"));
+        }
+        fn piece_boomed(&mut self, piece_index: usize, before: &Board, after: &Board) {
+            assert_eq!(
+                before.pieces[piece_index].height.boom(),
+                after.pieces[piece_index].height
+            );
+            self.0.borrow_mut().push(format!("boomed {piece_index}"));
+        }
+        fn piece_killed(&mut self, piece_index: usize, _before: &Board, _after: &Board) {
+            self.0.borrow_mut().push(format!("killed {piece_index}"));
+        }
+        fn piece_scored(&mut self, piece_index: usize, points: u8, before: &Board, after: &Board) {
+            assert_eq!(after.white_score, before.white_score + points);
+            self.0
+                .borrow_mut()
+                .push(format!("scored {piece_index} for {points}"));
+        }
+        fn game_over(&mut self, winner: &Winner, _before: &Board, _after: &Board) {
+            self.0.borrow_mut().push(format!("{winner:?} won"));
+        }
+    }
+
+    // Plays the moves it's given, in order
+    struct Scripted(Vec<Move>);
+    impl GamePlayer for Scripted {
+        fn decide(&mut self, _board: &Board, _color: &Color) -> Move {
+            self.0.remove(0)
+        }
+    }
+
+    #[test]
+    fn observes_every_kind_of_event() {
+        let (board, turn) = Board::from_fen("w27/8/8/8/8/8/b16b1/w17 w 0 0 -").unwrap();
+        let white = Scripted(vec![Move::Boom(4), Move::Score(1)]);
+        let mut game = Game::from_position(white, Scripted(vec![]), board, turn);
+        let events = Rc::new(RefCell::new(vec![]));
+        game.add_observer(Recorder(events.clone()));
+        game.play_turn();
+        game.apply_move(&Move::Zoom(5, "h3".try_into().unwrap()));
+        game.play_turn();
+        // Undoing isn't playing a move
+        game.undo();
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                "White Boom(4)",
+                "boomed 4",
+                "killed 4",
+                "Black Zoom(5, h3)",
+                "White Score(1)",
+                "scored 1 for 2",
+                "White won",
+            ]
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use crate::{Board, Color, Piece, RuleSet};
+
+/*
+ * A compact, lossless binary encoding of the full game state, for hash map keys and databases.
+ *
+ * A PackedBoard is 16 bytes, which are the little-endian bytes of a u128. Bits are numbered from the
+ * least significant bit of the u128:
+ *
+ *   0-63     The pieces, one byte each in slot order, as written by Piece::as_u8
+ *   64-71    White's score
+ *   72-79    Black's score
+ *   80-87    The color of each piece, one bit each in slot order, set for Black. The slot already
+ *            decides the color, so these have to match it.
+ *   88       The side to move, set for Black
+ *   89-90    The concession: 0 for none, 1 if White won by concession, 2 if Black won
+ *   91-106   Board::plies_since_progress
+ *   107-122  Board::plies_played
+ *   123      Unused, always 0
+ *   124-127  The version of the encoding, currently 1
+ *
+ * Dead pieces keep their squares, so boards that only differ by where a piece died have different
+ * encodings. Only boards using the standard rules can be packed.
+ */
+
+const VERSION: u8 = 1;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PackError {
+    UnsupportedRules,
+    UnknownVersion(u8),
+    InvalidConcession,
+    UnusedBitSet,
+    // The color bit of the piece in this slot doesn't match the slot
+    WrongColor(usize),
+}
+impl std::fmt::Display for PackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PackError::UnsupportedRules => write!(f, "Only the standard rules can be packed"),
+            PackError::UnknownVersion(version) => write!(f, "Unknown encoding version {version}"),
+            PackError::InvalidConcession => write!(f, "Invalid concession"),
+            PackError::UnusedBitSet => write!(f, "An unused bit is set"),
+            PackError::WrongColor(index) => write!(f, "Piece {index} has the wrong color"),
+        }
+    }
+}
+impl std::error::Error for PackError {}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct PackedBoard(pub [u8; 16]);
+impl From<PackedBoard> for u128 {
+    fn from(packed: PackedBoard) -> Self {
+        u128::from_le_bytes(packed.0)
+    }
+}
+impl From<u128> for PackedBoard {
+    fn from(key: u128) -> Self {
+        PackedBoard(key.to_le_bytes())
+    }
+}
+
+fn bits(key: u128, start: u32, len: u32) -> u128 {
+    (key >> start) & ((1 << len) - 1)
+}
+
+impl PackedBoard {
+    pub fn version(&self) -> u8 {
+        bits(u128::from(*self), 124, 4) as u8
+    }
+
+    pub fn unpack(&self) -> Result<(Board, Color), PackError> {
+        let key = u128::from(*self);
+        if self.version() != VERSION {
+            return Err(PackError::UnknownVersion(self.version()));
+        }
+        if bits(key, 123, 1) != 0 {
+            return Err(PackError::UnusedBitSet);
+        }
+        let rules = RuleSet::STANDARD;
+        let mut board = Board::empty(rules);
+        for index in 0..rules.piece_count() {
+            let color = match bits(key, 80 + index as u32, 1) {
+                0 => Color::White,
+                _ => Color::Black,
+            };
+            if color != rules.color_of(index) {
+                return Err(PackError::WrongColor(index));
+            }
+            board.pieces[index] = Piece::from_u8(color, bits(key, 8 * index as u32, 8) as u8);
+        }
+        board.white_score = bits(key, 64, 8) as u8;
+        board.black_score = bits(key, 72, 8) as u8;
+        let turn = match bits(key, 88, 1) {
+            0 => Color::White,
+            _ => Color::Black,
+        };
+        board.victory_by_concession = match bits(key, 89, 2) {
+            0 => None,
+            1 => Some(Color::White),
+            2 => Some(Color::Black),
+            _ => return Err(PackError::InvalidConcession),
+        };
+        board.plies_since_progress = bits(key, 91, 16) as u16;
+        board.plies_played = bits(key, 107, 16) as u16;
+        board.rehash(&turn);
+        Ok((board, turn))
+    }
+}
+
+impl Board {
+    pub fn pack(&self, turn: &Color) -> Result<PackedBoard, PackError> {
+        if !self.rules.is_standard() {
+            return Err(PackError::UnsupportedRules);
+        }
+        let mut key: u128 = 0;
+        for (index, piece) in self.pieces[..self.rules.piece_count()].iter().enumerate() {
+            key |= (piece.as_u8() as u128) << (8 * index);
+            if piece.color == Color::Black {
+                key |= 1 << (80 + index);
+            }
+        }
+        key |= (self.white_score as u128) << 64;
+        key |= (self.black_score as u128) << 72;
+        if turn == &Color::Black {
+            key |= 1 << 88;
+        }
+        let concession = match self.victory_by_concession {
+            None => 0,
+            Some(Color::White) => 1,
+            Some(Color::Black) => 2,
+        };
+        key |= concession << 89;
+        key |= (self.plies_since_progress as u128) << 91;
+        key |= (self.plies_played as u128) << 107;
+        key |= (VERSION as u128) << 124;
+        Ok(PackedBoard::from(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Move;
+    use rand::prelude::*;
+    use std::collections::HashMap;
+
+    fn assert_same(a: &Board, b: &Board, turn: &Color) {
+        assert_eq!(a.to_fen(turn), b.to_fen(turn));
+        assert_eq!(a.compress(), b.compress());
+        assert_eq!(a.plies_played, b.plies_played);
+        assert_eq!(a.plies_since_progress, b.plies_since_progress);
+        assert_eq!(a.zobrist(), b.zobrist());
+    }
+
+    #[test]
+    fn round_trips_random_games() {
+        let mut rng = thread_rng();
+        for _ in 0..50 {
+            let mut board = Board::default();
+            let mut turn = Color::White;
+            loop {
+                let (unpacked, unpacked_turn) = board.pack(&turn).unwrap().unpack().unwrap();
+                assert_eq!(unpacked_turn, turn);
+                assert_same(&unpacked, &board, &turn);
+                if board.winner().is_some() {
+                    break;
+                }
+                let mov = if rng.gen_ratio(1, 100) {
+                    Move::Concede(turn)
+                } else {
+                    board.legal_moves(&turn).choose(&mut rng).unwrap()
+                };
+                board = board.apply_move(&mov);
+                turn = turn.invert();
+            }
+        }
+    }
+
+    #[test]
+    fn works_as_a_key() {
+        let board = Board::default();
+        let white = board.pack(&Color::White).unwrap();
+        let black = board.pack(&Color::Black).unwrap();
+        assert_ne!(white, black);
+        assert_eq!(white.version(), 1);
+        assert_eq!(PackedBoard::from(u128::from(white)), white);
+        let mut seen = HashMap::new();
+        seen.insert(white, "white");
+        seen.insert(black, "black");
+        assert_eq!(
+            seen.get(&Board::default().pack(&Color::White).unwrap()),
+            Some(&"white")
+        );
+        let conceded = board.apply_move(&Move::Concede(Color::White));
+        let (unpacked, _) = conceded.pack(&Color::Black).unwrap().unpack().unwrap();
+        assert_eq!(unpacked.victory_by_concession, Some(Color::Black));
+    }
+
+    #[test]
+    fn pack_errors() {
+        let variant = Board::new(RuleSet::new(6, 6, 4));
+        assert_eq!(
+            variant.pack(&Color::White),
+            Err(PackError::UnsupportedRules)
+        );
+        let key = u128::from(Board::default().pack(&Color::White).unwrap());
+        let unversioned = key & !(0xf << 124);
+        assert_eq!(
+            PackedBoard::from(unversioned).unpack().err(),
+            Some(PackError::UnknownVersion(0))
+        );
+        assert_eq!(
+            PackedBoard::from(key | 3 << 89).unpack().err(),
+            Some(PackError::InvalidConcession)
+        );
+        assert_eq!(
+            PackedBoard::from(key | 1 << 123).unpack().err(),
+            Some(PackError::UnusedBitSet)
+        );
+        // Slot 1 is White's, and slot 5 is Black's
+        assert_eq!(
+            PackedBoard::from(key | 1 << 81).unpack().err(),
+            Some(PackError::WrongColor(1))
+        );
+        assert_eq!(
+            PackedBoard::from(key & !(1 << 85)).unpack().err(),
+            Some(PackError::WrongColor(5))
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{Board, Color, Move, MoveList};
+
+/*
+ * Performance tests, which count the leaf nodes of the game tree to a fixed depth.
+ *
+ * The counts are compared against known good values to catch move generation bugs. A position
+ * where the game is over has no moves, so it only counts as a leaf at depth 0. Concessions are
+ * never counted.
+ *
+ * Board::perft walks Board::legal_moves, and Board::perft_bitboard walks Board::generate_moves, so
+ * the two move generators can be checked against each other as well as against the known values.
+ */
+
+impl Board {
+    pub fn perft(&self, turn: &Color, depth: usize) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        if self.winner().is_some() {
+            return 0;
+        }
+        if depth == 1 {
+            return self.legal_moves(turn).count() as u64;
+        }
+        self.legal_moves(turn)
+            .map(|mov| self.apply_move(&mov).perft(&turn.invert(), depth - 1))
+            .sum()
+    }
+
+    // The same count, using the bitboard move generator and making moves in place
+    pub fn perft_bitboard(&mut self, turn: &Color, depth: usize) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        if self.winner().is_some() {
+            return 0;
+        }
+        let mut moves = MoveList::new();
+        self.generate_moves(turn, &mut moves);
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        moves
+            .iter()
+            .map(|mov| {
+                let undo = self.make_move(mov);
+                let count = self.perft_bitboard(&turn.invert(), depth - 1);
+                self.unmake_move(undo);
+                count
+            })
+            .sum()
+    }
+
+    // The perft count below every move, to narrow down where two move generators differ
+    pub fn perft_divide(&self, turn: &Color, depth: usize) -> Vec<(Move, u64)> {
+        if depth == 0 || self.winner().is_some() {
+            return vec![];
+        }
+        self.legal_moves(turn)
+            .map(|mov| (mov, self.apply_move(&mov).perft(&turn.invert(), depth - 1)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::STARTING_FEN;
+
+    fn check(fen: &str, counts: &[u64]) {
+        let (mut board, turn) = Board::from_fen(fen).unwrap();
+        for (depth, &count) in counts.iter().enumerate() {
+            assert_eq!(board.perft(&turn, depth), count, "{fen} at depth {depth}");
+            assert_eq!(
+                board.perft_bitboard(&turn, depth),
+                count,
+                "{fen} at depth {depth} with bitboards"
+            );
+        }
+    }
+
+    #[test]
+    fn starting_position() {
+        check(STARTING_FEN, &[1, 38, 1444, 65448]);
+    }
+
+    #[test]
+    fn score_zone_corners() {
+        // Pieces that can score by stepping off the corner, or straight up, or diagonally
+        check(
+            "7w1/w37/8/8/8/8/b37/7b1 w 0 0 -",
+            &[1, 16, 255, 4642, 83426],
+        );
+        check(
+            "7w1/w37/8/8/8/8/b37/7b1 b 0 0 -",
+            &[1, 16, 255, 4642, 83426],
+        );
+    }
+
+    #[test]
+    fn blocked_rays() {
+        // Every piece is touching its neighbors and the piece in front of it
+        check(
+            "8/8/8/2b3b3b3b32/2w3w3w3w32/8/8/8 w 0 0 -",
+            &[1, 52, 2868, 159706],
+        );
+    }
+
+    #[test]
+    fn mixed_heights() {
+        check("8/1b1b2b34/8/8/8/8/8/w1w2w35 w 2 1 -", &[1, 18, 558, 13451]);
+    }
+
+    #[test]
+    fn game_over() {
+        // Scoring with White's last piece ends the game
+        check("w17/8/8/8/8/8/8/7b1 w 0 0 -", &[1, 4, 12, 57, 342]);
+        check("8/8/8/8/8/8/8/8 w 1 0 -", &[1, 0, 0]);
+    }
+
+    #[test]
+    fn divide_adds_up() {
+        let (board, turn) = Board::from_fen(STARTING_FEN).unwrap();
+        let divide = board.perft_divide(&turn, 3);
+        assert_eq!(divide.len() as u64, board.perft(&turn, 1));
+        assert_eq!(
+            divide.iter().map(|(_, count)| count).sum::<u64>(),
+            board.perft(&turn, 3)
+        );
+        assert_eq!(board.perft_divide(&turn, 0), vec![]);
+    }
+}
@@ -0,0 +1,389 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::{Board, Color, FenError, Game, GamePlayer, Move, MoveError, NotationError, Winner};
+
+/*
+ * Game records, in the spirit of chess PGN.
+ *
+ *   [White "genius"]
+ *   [Black "random"]
+ *   [Date "2026.01.07"]
+ *   [Result "1-0"]
+ *   [Start "2b3b3b3b32/8/8/8/8/8/8/2w3w3w3w32 w 0 0 - 0 0"]
+ *   [TimeControl "10ms/move"]
+ *
+ *   1. c1-c4 c8-c5 2. c4xc5 ... 1-0
+ *
+ * Every tag is optional. A missing Start tag means the default starting position. Moves are
+ * written in algebraic notation and numbered every time White moves. The movetext ends with the
+ * result: 1-0, 0-1, 1/2-1/2 or * if the game is unfinished.
+ */
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GameRecord {
+    pub white: Option<String>,
+    pub black: Option<String>,
+    pub date: Option<String>,
+    pub result: Option<Winner>,
+    // The starting position as a FEN string, if it isn't the default
+    pub start: Option<String>,
+    pub time_control: Option<String>,
+    // Any other tags, in the order they were read
+    pub extra_tags: Vec<(String, String)>,
+    pub moves: Vec<Move>,
+}
+
+#[derive(Debug)]
+pub enum RecordError {
+    MalformedTag(String),
+    BadResult(String),
+    Fen(FenError),
+    // The ply (counting from 0) of the offending move
+    Notation(usize, NotationError),
+    OutOfTurn(usize, String),
+    IllegalMove(usize, MoveError),
+    Io(std::io::Error),
+}
+impl std::fmt::Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordError::MalformedTag(line) => write!(f, "Malformed tag {line}"),
+            RecordError::BadResult(result) => write!(f, "Invalid result \"{result}\""),
+            RecordError::Fen(err) => write!(f, "Invalid start position: {err}"),
+            RecordError::Notation(ply, err) => write!(f, "Ply {ply}: {err}"),
+            RecordError::OutOfTurn(ply, mov) => write!(f, "Ply {ply}: {mov} is out of turn"),
+            RecordError::IllegalMove(ply, err) => write!(f, "Ply {ply}: {err}"),
+            RecordError::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+impl std::error::Error for RecordError {}
+impl From<FenError> for RecordError {
+    fn from(err: FenError) -> Self {
+        RecordError::Fen(err)
+    }
+}
+impl From<std::io::Error> for RecordError {
+    fn from(err: std::io::Error) -> Self {
+        RecordError::Io(err)
+    }
+}
+
+fn result_str(result: &Option<Winner>) -> &'static str {
+    match result {
+        Some(Winner::White) => "1-0",
+        Some(Winner::Black) => "0-1",
+        Some(Winner::Draw) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+fn parse_result(s: &str) -> Option<Option<Winner>> {
+    match s {
+        "1-0" => Some(Some(Winner::White)),
+        "0-1" => Some(Some(Winner::Black)),
+        "1/2-1/2" => Some(Some(Winner::Draw)),
+        "*" => Some(None),
+        _ => None,
+    }
+}
+
+// The color making a move, or None if the move doesn't make sense on this board
+fn mover(board: &Board, mov: &Move) -> Option<Color> {
+    match mov {
+        Move::Zoom(index, _) | Move::Score(index) => board.pieces.get(*index).map(|p| p.color),
+        Move::Boom(index) => board.pieces.get(*index).map(|p| p.color.invert()),
+        Move::Concede(color) => Some(*color),
+    }
+}
+
+impl GameRecord {
+    pub fn start_position(&self) -> Result<(Board, Color), FenError> {
+        match &self.start {
+            Some(fen) => Board::from_fen(fen),
+            None => Ok((Board::default(), Color::White)),
+        }
+    }
+
+    // Replays every move, returning the final board and the side to move
+    pub fn replay(&self) -> Result<(Board, Color), RecordError> {
+        let (mut board, mut turn) = self.start_position()?;
+        for (ply, mov) in self.moves.iter().enumerate() {
+            board = board
+                .try_apply_move(mov, &turn)
+                .map_err(|err| RecordError::IllegalMove(ply, err))?;
+            turn = turn.invert();
+        }
+        Ok((board, turn))
+    }
+
+    pub fn into_game<W, B>(
+        &self,
+        white_player: W,
+        black_player: B,
+    ) -> Result<Game<W, B>, RecordError>
+    where
+        W: GamePlayer,
+        B: GamePlayer,
+    {
+        let (board, turn) = self.start_position()?;
+        let mut game = Game::from_position(white_player, black_player, board, turn);
+        for (ply, mov) in self.moves.iter().enumerate() {
+            game.try_apply_move(mov)
+                .map_err(|err| RecordError::IllegalMove(ply, err))?;
+        }
+        Ok(game)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<GameRecord, RecordError> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), RecordError> {
+        std::fs::write(path, self.to_text()?)?;
+        Ok(())
+    }
+
+    // The record as text, checking that the start position is valid and every move is legal
+    pub fn to_text(&self) -> Result<String, RecordError> {
+        let mut text = String::new();
+        let tags = [
+            ("White", self.white.as_deref()),
+            ("Black", self.black.as_deref()),
+            ("Date", self.date.as_deref()),
+            ("Result", Some(result_str(&self.result))),
+            ("Start", self.start.as_deref()),
+            ("TimeControl", self.time_control.as_deref()),
+        ];
+        for (name, value) in tags {
+            if let Some(value) = value {
+                text.push_str(&format!("[{name} \"{value}\"]\n"));
+            }
+        }
+        for (name, value) in self.extra_tags.iter() {
+            text.push_str(&format!("[{name} \"{value}\"]\n"));
+        }
+        text.push('\n');
+
+        let (mut board, mut turn) = self.start_position()?;
+        // If Black moves first, White's first move is in the second ply of move 1
+        let offset = if turn == Color::White { 0 } else { 1 };
+        let mut tokens = vec![];
+        for (ply, mov) in self.moves.iter().enumerate() {
+            if turn == Color::White {
+                tokens.push(format!("{}.", (ply + offset) / 2 + 1));
+            } else if ply == 0 {
+                tokens.push("1...".to_string());
+            }
+            let next = board
+                .try_apply_move(mov, &turn)
+                .map_err(|err| RecordError::IllegalMove(ply, err))?;
+            tokens.push(mov.to_notation(&board));
+            board = next;
+            turn = turn.invert();
+        }
+        tokens.push(result_str(&self.result).to_string());
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + token.len() >= 80 {
+                text.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                text.push(' ');
+                line_length += 1;
+            }
+            text.push_str(&token);
+            line_length += token.len();
+        }
+        text.push('\n');
+        Ok(text)
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = RecordError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut record = GameRecord::default();
+        let mut movetext = vec![];
+        for line in s.lines().map(|line| line.trim()) {
+            if let Some(tag) = line.strip_prefix('[') {
+                let malformed = || RecordError::MalformedTag(line.to_string());
+                let (name, value) = tag
+                    .strip_suffix(']')
+                    .and_then(|tag| tag.split_once(' '))
+                    .ok_or_else(malformed)?;
+                let value = value
+                    .trim()
+                    .strip_prefix('"')
+                    .and_then(|value| value.strip_suffix('"'))
+                    .ok_or_else(malformed)?
+                    .to_string();
+                match name {
+                    "White" => record.white = Some(value),
+                    "Black" => record.black = Some(value),
+                    "Date" => record.date = Some(value),
+                    "Result" => {
+                        record.result =
+                            parse_result(&value).ok_or(RecordError::BadResult(value))?;
+                    }
+                    "Start" => record.start = Some(value),
+                    "TimeControl" => record.time_control = Some(value),
+                    _ => record.extra_tags.push((name.to_string(), value)),
+                }
+            } else {
+                movetext.extend(line.split_whitespace());
+            }
+        }
+
+        let (mut board, mut turn) = record.start_position()?;
+        for token in movetext {
+            if token.ends_with('.') {
+                // Move numbers are only there for humans
+                continue;
+            }
+            if let Some(result) = parse_result(token) {
+                // The movetext result is redundant with the Result tag, which takes precedence
+                record.result = record.result.clone().or(result);
+                continue;
+            }
+            let ply = record.moves.len();
+            let mov = Move::from_notation(token, &board)
+                .map_err(|err| RecordError::Notation(ply, err))?;
+            if mover(&board, &mov) != Some(turn) {
+                return Err(RecordError::OutOfTurn(ply, token.to_string()));
+            }
+            // Anything else to_text would refuse to write back out, like moves after the end
+            board = board
+                .try_apply_move(&mov, &turn)
+                .map_err(|err| RecordError::IllegalMove(ply, err))?;
+            turn = turn.invert();
+            record.moves.push(mov);
+        }
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_record() -> GameRecord {
+        let moves = [
+            "c1-c4", "c8-c5", "c4xc5", "d8-d5", "e1-e4", "d5xe4", "d1-d4",
+        ];
+        let mut board = Board::default();
+        let moves = moves
+            .iter()
+            .map(|notation| {
+                let mov = Move::from_notation(notation, &board).unwrap();
+                board = board.apply_move(&mov);
+                mov
+            })
+            .collect();
+        GameRecord {
+            white: Some("genius".to_string()),
+            black: Some("random".to_string()),
+            date: Some("2026.01.07".to_string()),
+            result: None,
+            start: None,
+            time_control: Some("10ms/move".to_string()),
+            extra_tags: vec![("Round".to_string(), "3".to_string())],
+            moves,
+        }
+    }
+
+    #[test]
+    fn record_round_trip() {
+        let record = sample_record();
+        let text = record.to_text().unwrap();
+        assert!(text.contains("1. c1-c4 c8-c5 2. c4xc5 d8-d5 3. e1-e4 d5xe4 4. d1-d4 *"));
+        assert_eq!(text.parse::<GameRecord>().unwrap(), record);
+    }
+
+    #[test]
+    fn record_replays_to_winner() {
+        let text = "[Result \"1-0\"]\n[Start \"8/8/8/8/8/w3b16/8/8 w 1 0 -\"]\n\n1. a3xb3 1-0\n";
+        let record: GameRecord = text.parse().unwrap();
+        assert_eq!(record.result, Some(Winner::White));
+        let (board, turn) = record.replay().unwrap();
+        assert_eq!(turn, Color::Black);
+        assert_eq!(board.winner(), record.result);
+        assert_eq!(record.to_text().unwrap(), text);
+    }
+
+    #[test]
+    fn record_black_to_move() {
+        let text = "[Result \"0-1\"]\n[Start \"8/8/8/8/8/w1b16/8/8 b 0 1 -\"]\n\n1... b3xa3 0-1\n";
+        let record: GameRecord = text.parse().unwrap();
+        assert_eq!(record.moves, vec![Move::Boom(0)]);
+        assert_eq!(record.to_text().unwrap(), text);
+    }
+
+    #[test]
+    fn record_errors() {
+        assert!(matches!(
+            "[White genius]".parse::<GameRecord>(),
+            Err(RecordError::MalformedTag(_))
+        ));
+        assert!(matches!(
+            "[Result \"2-0\"]".parse::<GameRecord>(),
+            Err(RecordError::BadResult(_))
+        ));
+        assert!(matches!(
+            "1. c1-c4 c7-c5".parse::<GameRecord>(),
+            Err(RecordError::Notation(1, NotationError::NoPieceAt(_)))
+        ));
+        assert!(matches!(
+            "1. c8-c5".parse::<GameRecord>(),
+            Err(RecordError::OutOfTurn(0, _))
+        ));
+        // Black can't concede a game White has already won
+        assert!(matches!(
+            "[Start \"8/8/8/8/8/w3b16/8/8 w 1 0 -\"]\n\n1. a3xb3 concede-b 1-0\n"
+                .parse::<GameRecord>(),
+            Err(RecordError::IllegalMove(1, MoveError::GameOver))
+        ));
+    }
+
+    #[test]
+    fn invalid_records_are_not_written() {
+        let mut record = sample_record();
+        record.start = Some("not a fen".to_string());
+        assert!(matches!(record.to_text(), Err(RecordError::Fen(_))));
+        let path = std::env::temp_dir().join("baz_invalid_record.txt");
+        assert!(matches!(record.save(&path), Err(RecordError::Fen(_))));
+        assert!(!path.exists());
+        // Black's piece can't zoom on White's turn
+        let mut record = sample_record();
+        record
+            .moves
+            .insert(0, Move::Zoom(4, "c5".try_into().unwrap()));
+        assert!(matches!(
+            record.to_text(),
+            Err(RecordError::IllegalMove(0, MoveError::WrongSide))
+        ));
+    }
+
+    struct NoPlayer();
+    impl GamePlayer for NoPlayer {
+        fn decide(&mut self, _board: &Board, _color: &Color) -> Move {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn invalid_records_are_not_replayed() {
+        let mut record = sample_record();
+        record.moves.insert(3, Move::Boom(99));
+        assert!(matches!(
+            record.replay(),
+            Err(RecordError::IllegalMove(3, MoveError::IndexOutOfRange(99)))
+        ));
+        assert!(matches!(
+            record.into_game(NoPlayer(), NoPlayer()),
+            Err(RecordError::IllegalMove(3, MoveError::IndexOutOfRange(99)))
+        ));
+    }
+}
@@ -0,0 +1,206 @@
+use std::ops::Range;
+
+use crate::{Color, DrawRules, Height};
+
+/*
+ * Rule variants.
+ *
+ * Boards are at most 8x8 and each side has at most 8 pieces. Squares keep the same indices no matter
+ * the board size (a1 is 0, a2 is 8, ...), so a smaller board simply leaves some squares unused.
+ *
+ * White's pieces take the first pieces_per_side slots of Board::pieces, and Black's pieces take the
+ * next pieces_per_side slots. Any remaining slots are always dead. White starts on the first rank
+ * and Black starts on the last rank, on the same files and with the same heights.
+ */
+
+pub const MAX_PIECES_PER_SIDE: usize = 8;
+pub const MAX_PIECES: usize = 2 * MAX_PIECES_PER_SIDE;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct RuleSet {
+    pub width: i8,
+    pub height: i8,
+    pub pieces_per_side: usize,
+    // The file and height each piece starts with. Only the first pieces_per_side are used.
+    pub start_files: [i8; MAX_PIECES_PER_SIDE],
+    pub start_heights: [Height; MAX_PIECES_PER_SIDE],
+    // If set, pieces can only score by stepping off the far rank, not by zooming from further back
+    pub score_from_far_rank_only: bool,
+    pub draws: DrawRules,
+}
+impl Default for RuleSet {
+    fn default() -> RuleSet {
+        RuleSet::STANDARD
+    }
+}
+impl RuleSet {
+    pub const STANDARD: RuleSet = RuleSet {
+        width: 8,
+        height: 8,
+        pieces_per_side: 4,
+        start_files: [2, 3, 4, 5, 0, 0, 0, 0],
+        start_heights: [Height::Three; MAX_PIECES_PER_SIDE],
+        score_from_far_rank_only: false,
+        draws: DrawRules::STANDARD,
+    };
+
+    // A board of the given size with all pieces at height three, centered on the back ranks
+    pub fn new(width: i8, height: i8, pieces_per_side: usize) -> RuleSet {
+        assert!((1..=8).contains(&width) && (2..=8).contains(&height));
+        assert!((1..=MAX_PIECES_PER_SIDE).contains(&pieces_per_side));
+        assert!(pieces_per_side <= width as usize);
+        let first_file = (width - pieces_per_side as i8) / 2;
+        let mut start_files = [0; MAX_PIECES_PER_SIDE];
+        for (i, file) in start_files.iter_mut().take(pieces_per_side).enumerate() {
+            *file = first_file + i as i8;
+        }
+        RuleSet {
+            width,
+            height,
+            pieces_per_side,
+            start_files,
+            start_heights: [Height::Three; MAX_PIECES_PER_SIDE],
+            score_from_far_rank_only: false,
+            draws: DrawRules::STANDARD,
+        }
+    }
+
+    pub fn with_start_heights(mut self, heights: &[Height]) -> RuleSet {
+        assert_eq!(heights.len(), self.pieces_per_side);
+        self.start_heights[..heights.len()].copy_from_slice(heights);
+        self
+    }
+
+    pub fn with_far_rank_scoring(mut self) -> RuleSet {
+        self.score_from_far_rank_only = true;
+        self
+    }
+
+    pub fn with_draw_rules(mut self, draws: DrawRules) -> RuleSet {
+        self.draws = draws;
+        self
+    }
+
+    pub fn is_standard(&self) -> bool {
+        self == &RuleSet::STANDARD
+    }
+
+    // Whether pieces move and score just like they do on the standard board. The starting
+    // position and draw rules may still differ.
+    pub fn has_standard_board(&self) -> bool {
+        let standard = RuleSet::STANDARD;
+        self.width == standard.width
+            && self.height == standard.height
+            && self.pieces_per_side == standard.pieces_per_side
+            && self.score_from_far_rank_only == standard.score_from_far_rank_only
+    }
+
+    pub fn piece_count(&self) -> usize {
+        2 * self.pieces_per_side
+    }
+
+    // The slots in Board::pieces that belong to color
+    pub fn pieces(&self, color: &Color) -> Range<usize> {
+        match color {
+            Color::White => 0..self.pieces_per_side,
+            Color::Black => self.pieces_per_side..self.piece_count(),
+        }
+    }
+
+    pub fn color_of(&self, piece_index: usize) -> Color {
+        if piece_index < self.pieces_per_side {
+            Color::White
+        } else {
+            Color::Black
+        }
+    }
+
+    pub fn contains(&self, x: i8, y: i8) -> bool {
+        (0..self.width).contains(&x) && (0..self.height).contains(&y)
+    }
+
+    // The rank color starts on
+    pub fn back_rank(&self, color: &Color) -> i8 {
+        match color {
+            Color::White => 0,
+            Color::Black => self.height - 1,
+        }
+    }
+
+    // The number of squares between a piece and the score zone, counting the score zone itself
+    pub fn distance_to_score(&self, color: &Color, y: i8) -> i8 {
+        match color {
+            Color::White => self.height - y,
+            Color::Black => 1 + y,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Board, Move, Winner};
+
+    #[test]
+    fn standard_rules_are_the_default_game() {
+        let board = Board::new(RuleSet::new(8, 8, 4));
+        assert_eq!(board.rules, RuleSet::STANDARD);
+        assert_eq!(board.compress(), Board::default().compress());
+    }
+
+    #[test]
+    fn small_board_with_five_pieces() {
+        let rules = RuleSet::new(6, 6, 5).with_start_heights(&[
+            Height::One,
+            Height::Two,
+            Height::Three,
+            Height::Two,
+            Height::One,
+        ]);
+        let board = Board::new(rules);
+        assert_eq!(board.pieces[0].position, "a1".try_into().unwrap());
+        assert_eq!(board.pieces[4].position, "e1".try_into().unwrap());
+        assert_eq!(board.pieces[4].height, Height::One);
+        assert_eq!(board.pieces[5].color, Color::Black);
+        assert_eq!(board.pieces[7].position, "c6".try_into().unwrap());
+        assert_eq!(board.pieces[7].height, Height::Three);
+        assert_eq!(board.pieces[10].height, Height::Dead);
+        assert_eq!(board.legal_moves(&Color::White).count(), 25);
+        // The middle piece can't go off the side of the board
+        assert!(board
+            .legal_moves(&Color::White)
+            .all(|m| !matches!(m, Move::Zoom(_, p) if p.x() >= 6 || p.y() >= 6)));
+        // The e1 piece is the fifth white piece
+        assert!(board
+            .legal_moves(&Color::White)
+            .any(|m| m == Move::Zoom(4, "e2".try_into().unwrap())));
+    }
+
+    #[test]
+    fn scoring_on_a_small_board() {
+        let mut board = Board::new(RuleSet::new(6, 6, 1));
+        board.pieces[0].position = "c4".try_into().unwrap();
+        board.pieces[1].position = "a1".try_into().unwrap();
+        assert!(board
+            .legal_moves(&Color::White)
+            .any(|m| m == Move::Score(0)));
+        let board = board.apply_move(&Move::Score(0));
+        assert_eq!(board.white_score, 3);
+        assert_eq!(board.winner(), Some(Winner::White));
+    }
+
+    #[test]
+    fn far_rank_scoring() {
+        let mut board = Board::new(RuleSet::STANDARD.with_far_rank_scoring());
+        board.pieces[0].position = "c6".try_into().unwrap();
+        assert!(!board
+            .legal_moves(&Color::White)
+            .any(|m| m == Move::Score(0)));
+        board.pieces[0].position = "c8".try_into().unwrap();
+        assert!(board
+            .legal_moves(&Color::White)
+            .any(|m| m == Move::Score(0)));
+    }
+}
@@ -0,0 +1,228 @@
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Board, Color, Height, Piece, Position, RuleSet};
+
+/*
+ * Serde support, enabled with the serde feature.
+ *
+ * The representations, shown as JSON:
+ *   Color     "white" or "black"
+ *   Winner    "white", "black" or "draw"
+ *   Position  the square name, like "c4"
+ *   Height    the number of stories, 0 (dead) to 3
+ *   Piece     {"color": "white", "position": "c4", "height": 3}
+ *   Move      {"zoom": [0, "c4"]}, {"boom": 5}, {"score": 1} or {"concede": "white"}
+ *   RuleSet   every field, by name. Any that are missing are taken from the standard rules.
+ *   Board     {"rules": {...}, "pieces": [...], "white_score": 0, "black_score": 0,
+ *              "victory_by_concession": null, "plies_played": 0, "plies_since_progress": 0}
+ *
+ * A board only lists the pieces that its rules use, White's first. The rules and counters may be
+ * left out, in which case the standard rules are used and the counters start at zero. Like
+ * Board::decompress, a deserialized board's zobrist key assumes White is to move.
+ */
+
+impl Serialize for Position {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+impl<'de> Deserialize<'de> for Position {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let square = String::deserialize(deserializer)?;
+        Position::try_from(square.as_str())
+            .map_err(|_| D::Error::custom(format!("invalid square \"{square}\"")))
+    }
+}
+
+impl Serialize for Height {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(u8::from(self))
+    }
+}
+impl<'de> Deserialize<'de> for Height {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let height = u8::deserialize(deserializer)?;
+        Height::try_from(height).map_err(|_| D::Error::custom(format!("invalid height {height}")))
+    }
+}
+
+#[derive(Serialize)]
+struct BoardRef<'a> {
+    rules: &'a RuleSet,
+    pieces: &'a [Piece],
+    white_score: u8,
+    black_score: u8,
+    victory_by_concession: Option<Color>,
+    plies_played: u16,
+    plies_since_progress: u16,
+}
+
+#[derive(Deserialize)]
+struct BoardData {
+    #[serde(default)]
+    rules: RuleSet,
+    pieces: Vec<Piece>,
+    white_score: u8,
+    black_score: u8,
+    #[serde(default)]
+    victory_by_concession: Option<Color>,
+    #[serde(default)]
+    plies_played: u16,
+    #[serde(default)]
+    plies_since_progress: u16,
+}
+
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        BoardRef {
+            rules: &self.rules,
+            pieces: &self.pieces[..self.rules.piece_count()],
+            white_score: self.white_score,
+            black_score: self.black_score,
+            victory_by_concession: self.victory_by_concession,
+            plies_played: self.plies_played,
+            plies_since_progress: self.plies_since_progress,
+        }
+        .serialize(serializer)
+    }
+}
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = BoardData::deserialize(deserializer)?;
+        let rules = data.rules;
+        if data.pieces.len() != rules.piece_count() {
+            return Err(D::Error::custom(format!(
+                "expected {} pieces, found {}",
+                rules.piece_count(),
+                data.pieces.len()
+            )));
+        }
+        let mut board = Board::empty(rules);
+        for (index, piece) in data.pieces.into_iter().enumerate() {
+            if piece.color != rules.color_of(index) {
+                return Err(D::Error::custom(format!(
+                    "piece {index} has the wrong color"
+                )));
+            }
+            board.pieces[index] = piece;
+        }
+        board.white_score = data.white_score;
+        board.black_score = data.black_score;
+        board.victory_by_concession = data.victory_by_concession;
+        board.plies_played = data.plies_played;
+        board.plies_since_progress = data.plies_since_progress;
+        board.rehash(&Color::White);
+        Ok(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DrawRules, Move, Winner};
+
+    fn round_trip<T>(value: &T) -> T
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+    {
+        let json = serde_json::to_string(value).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn colors_and_winners() {
+        assert_eq!(serde_json::to_string(&Color::White).unwrap(), "\"white\"");
+        for color in [Color::White, Color::Black] {
+            assert_eq!(round_trip(&color), color);
+        }
+        assert_eq!(serde_json::to_string(&Winner::Draw).unwrap(), "\"draw\"");
+        for winner in [Winner::White, Winner::Black, Winner::Draw] {
+            assert_eq!(round_trip(&winner), winner);
+        }
+    }
+
+    #[test]
+    fn positions() {
+        let c4: Position = "c4".try_into().unwrap();
+        assert_eq!(serde_json::to_string(&c4).unwrap(), "\"c4\"");
+        for index in 0..64u8 {
+            let position = Position::from(index);
+            assert_eq!(round_trip(&position), position);
+        }
+        assert!(serde_json::from_str::<Position>("\"i1\"").is_err());
+        assert!(serde_json::from_str::<Position>("\"a9\"").is_err());
+    }
+
+    #[test]
+    fn heights_and_pieces() {
+        for height in [Height::Dead, Height::One, Height::Two, Height::Three] {
+            assert_eq!(round_trip(&height), height);
+        }
+        assert!(serde_json::from_str::<Height>("4").is_err());
+        let piece = Board::default().pieces[5].clone();
+        let json = serde_json::to_string(&piece).unwrap();
+        assert_eq!(json, r#"{"color":"black","position":"d8","height":3}"#);
+        let parsed: Piece = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.color, piece.color);
+        assert_eq!(parsed.position, piece.position);
+        assert_eq!(parsed.height, piece.height);
+    }
+
+    #[test]
+    fn moves() {
+        let c4 = "c4".try_into().unwrap();
+        let moves = [
+            (Move::Zoom(0, c4), r#"{"zoom":[0,"c4"]}"#),
+            (Move::Boom(5), r#"{"boom":5}"#),
+            (Move::Score(1), r#"{"score":1}"#),
+            (Move::Concede(Color::Black), r#"{"concede":"black"}"#),
+        ];
+        for (mov, json) in moves {
+            assert_eq!(serde_json::to_string(&mov).unwrap(), json);
+            assert_eq!(round_trip(&mov), mov);
+        }
+    }
+
+    #[test]
+    fn rules() {
+        let rules = RuleSet::new(6, 6, 5)
+            .with_far_rank_scoring()
+            .with_draw_rules(DrawRules::NONE);
+        assert_eq!(round_trip(&rules), rules);
+        assert_eq!(round_trip(&RuleSet::STANDARD), RuleSet::STANDARD);
+    }
+
+    #[test]
+    fn boards() {
+        let board = Board::default()
+            .apply_move(&Move::Zoom(0, "c4".try_into().unwrap()))
+            .apply_move(&Move::Zoom(4, "c5".try_into().unwrap()))
+            .apply_move(&Move::Boom(4))
+            .apply_move(&Move::Concede(Color::Black));
+        let parsed = round_trip(&board);
+        assert_eq!(parsed.to_fen(&Color::White), board.to_fen(&Color::White));
+        assert_eq!(parsed.rules, board.rules);
+        assert_eq!(parsed.plies_played, 4);
+        assert_eq!(parsed.plies_since_progress, 1);
+        assert_eq!(parsed.zobrist(), parsed.compute_zobrist(&Color::White));
+
+        let variant = Board::new(RuleSet::new(6, 6, 5));
+        let parsed = round_trip(&variant);
+        assert_eq!(parsed.to_fen(&Color::White), variant.to_fen(&Color::White));
+
+        // The rules and counters are optional
+        let json = serde_json::to_value(Board::default()).unwrap();
+        let minimal = serde_json::json!({
+            "pieces": json["pieces"],
+            "white_score": 0,
+            "black_score": 0,
+        });
+        let parsed: Board = serde_json::from_value(minimal).unwrap();
+        assert_eq!(parsed.zobrist(), Board::default().zobrist());
+        // But the pieces have to match the rules
+        let mut missing = json.clone();
+        missing["pieces"].as_array_mut().unwrap().pop();
+        assert!(serde_json::from_value::<Board>(missing).is_err());
+    }
+}
@@ -0,0 +1,271 @@
+use crate::{Board, Color, Move, Position, RuleSet};
+
+/*
+ * Symmetries of the game.
+ *
+ * Mirroring the board left to right doesn't change anything about the position. Neither does
+ * swapping the colors and flipping the board upside down, as long as the other side is to move.
+ * Pieces keep their slots when mirrored, and swap slots with the matching piece of the other color
+ * when the colors are swapped (piece 0 becomes piece rules.pieces_per_side and vice versa).
+ *
+ * Both transforms undo themselves and don't depend on the order they're applied in, so a Symmetry
+ * is its own inverse. To use a canonical board in a transposition table, look up the canonical
+ * board and map any stored move back through the same symmetry.
+ */
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Symmetry {
+    pub mirror: bool,
+    pub swap_colors: bool,
+}
+impl Symmetry {
+    pub const IDENTITY: Symmetry = Symmetry {
+        mirror: false,
+        swap_colors: false,
+    };
+
+    pub fn apply(&self, board: &Board) -> Board {
+        let mut board = board.clone();
+        if self.mirror {
+            board = board.mirrored();
+        }
+        if self.swap_colors {
+            board = board.color_swapped();
+        }
+        board
+    }
+
+    pub fn apply_move(&self, mov: &Move, rules: &RuleSet) -> Move {
+        let mut mov = *mov;
+        if self.mirror {
+            mov = mov.mirrored(rules);
+        }
+        if self.swap_colors {
+            mov = mov.color_swapped(rules);
+        }
+        mov
+    }
+
+    pub fn apply_turn(&self, turn: &Color) -> Color {
+        if self.swap_colors {
+            turn.invert()
+        } else {
+            *turn
+        }
+    }
+}
+
+fn mirror_position(position: &Position, rules: &RuleSet) -> Position {
+    (rules.width - 1 - position.x(), position.y()).into()
+}
+
+fn flip_position(position: &Position, rules: &RuleSet) -> Position {
+    (position.x(), rules.height - 1 - position.y()).into()
+}
+
+// The slot of the matching piece of the other color
+fn swap_slot(index: usize, rules: &RuleSet) -> usize {
+    if index < rules.pieces_per_side {
+        index + rules.pieces_per_side
+    } else {
+        index - rules.pieces_per_side
+    }
+}
+
+impl Move {
+    pub fn mirrored(&self, rules: &RuleSet) -> Move {
+        match self {
+            Move::Zoom(index, position) => Move::Zoom(*index, mirror_position(position, rules)),
+            _ => *self,
+        }
+    }
+
+    pub fn color_swapped(&self, rules: &RuleSet) -> Move {
+        match self {
+            Move::Zoom(index, position) => {
+                Move::Zoom(swap_slot(*index, rules), flip_position(position, rules))
+            }
+            Move::Boom(index) => Move::Boom(swap_slot(*index, rules)),
+            Move::Score(index) => Move::Score(swap_slot(*index, rules)),
+            Move::Concede(color) => Move::Concede(color.invert()),
+        }
+    }
+}
+
+impl Board {
+    // The board mirrored left to right, with the same side to move
+    pub fn mirrored(&self) -> Board {
+        let mut board = self.clone();
+        for piece in board.pieces[..self.rules.piece_count()].iter_mut() {
+            piece.position = mirror_position(&piece.position, &self.rules);
+        }
+        board.rehash_from(self, false);
+        board
+    }
+
+    // The board flipped upside down with the colors swapped. The other side is to move.
+    pub fn color_swapped(&self) -> Board {
+        let mut board = self.clone();
+        for index in 0..self.rules.piece_count() {
+            let piece = &self.pieces[index];
+            let swapped = &mut board.pieces[swap_slot(index, &self.rules)];
+            swapped.color = piece.color.invert();
+            swapped.position = flip_position(&piece.position, &self.rules);
+            swapped.height = piece.height;
+        }
+        board.white_score = self.black_score;
+        board.black_score = self.white_score;
+        board.victory_by_concession = self.victory_by_concession.map(|c| c.invert());
+        board.rehash_from(self, true);
+        board
+    }
+
+    // A representative of every position equivalent to this one. The canonical board always has
+    // White to move, and applying the returned symmetry to it gives back this board.
+    pub fn canonical(&self, turn: &Color) -> (Board, Symmetry) {
+        let swap_colors = turn == &Color::Black;
+        let board = if swap_colors {
+            self.color_swapped()
+        } else {
+            self.clone()
+        };
+        let mirrored = board.mirrored();
+        if mirrored.zobrist() < board.zobrist() {
+            (
+                mirrored,
+                Symmetry {
+                    mirror: true,
+                    swap_colors,
+                },
+            )
+        } else {
+            (
+                board,
+                Symmetry {
+                    mirror: false,
+                    swap_colors,
+                },
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Winner, STARTING_FEN};
+    use rand::prelude::*;
+
+    fn sorted_moves(moves: impl Iterator<Item = Move>) -> Vec<String> {
+        let mut moves: Vec<String> = moves.map(|m| format!("{m:?}")).collect();
+        moves.sort();
+        moves
+    }
+
+    fn check_symmetry(board: &Board, turn: &Color, symmetry: Symmetry) {
+        let transformed = symmetry.apply(board);
+        let transformed_turn = symmetry.apply_turn(turn);
+        assert_eq!(
+            transformed.zobrist(),
+            transformed.compute_zobrist(&transformed_turn)
+        );
+        assert_eq!(
+            sorted_moves(transformed.legal_moves(&transformed_turn)),
+            sorted_moves(
+                board
+                    .legal_moves(turn)
+                    .map(|m| symmetry.apply_move(&m, &board.rules))
+            )
+        );
+        assert_eq!(
+            transformed.winner().map(|w| w.color()),
+            board
+                .winner()
+                .map(|w| w.color().map(|c| symmetry.apply_turn(&c)))
+        );
+        // Symmetries undo themselves
+        let restored = symmetry.apply(&transformed);
+        assert_eq!(restored.to_fen(turn), board.to_fen(turn));
+        assert_eq!(restored.zobrist(), board.zobrist());
+    }
+
+    #[test]
+    fn symmetries_preserve_the_game() {
+        let mut rng = thread_rng();
+        let symmetries = [
+            Symmetry::IDENTITY,
+            Symmetry {
+                mirror: true,
+                swap_colors: false,
+            },
+            Symmetry {
+                mirror: false,
+                swap_colors: true,
+            },
+            Symmetry {
+                mirror: true,
+                swap_colors: true,
+            },
+        ];
+        for rules in [RuleSet::STANDARD, RuleSet::new(5, 6, 3)] {
+            for _ in 0..10 {
+                let mut board = Board::new(rules);
+                let mut turn = Color::White;
+                while board.winner().is_none() {
+                    for symmetry in symmetries {
+                        check_symmetry(&board, &turn, symmetry);
+                    }
+                    let mov = board.legal_moves(&turn).choose(&mut rng).unwrap();
+                    board = board.apply_move(&mov);
+                    turn = turn.invert();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mirrored_and_swapped_boards() {
+        let (board, _) = Board::from_fen("8/8/2b25/8/8/8/w36w1/8 w 3 1 -").unwrap();
+        assert_eq!(
+            board.mirrored().to_fen(&Color::White),
+            "8/8/5b22/8/8/8/w16w3/8 w 3 1 - 0 0"
+        );
+        let swapped = board.color_swapped();
+        assert_eq!(
+            swapped.to_fen(&Color::Black),
+            "8/b36b1/8/8/8/2w25/8/8 b 1 3 - 0 0"
+        );
+        assert_eq!(
+            Move::Zoom(0, "a3".try_into().unwrap()).color_swapped(&board.rules),
+            Move::Zoom(4, "a6".try_into().unwrap())
+        );
+        assert_eq!(Move::Boom(4).color_swapped(&board.rules), Move::Boom(0));
+        assert_eq!(board.winner(), None);
+        let won = board.apply_move(&Move::Concede(Color::Black));
+        assert_eq!(won.color_swapped().winner(), Some(Winner::Black));
+    }
+
+    #[test]
+    fn canonical_merges_equivalent_positions() {
+        let (board, turn) = Board::from_fen(STARTING_FEN).unwrap();
+        let board = board.apply_move(&Move::Zoom(0, "c4".try_into().unwrap()));
+        let turn = turn.invert();
+        let (canonical, symmetry) = board.canonical(&turn);
+        assert!(symmetry.swap_colors);
+        assert_eq!(
+            canonical.zobrist(),
+            canonical.compute_zobrist(&Color::White)
+        );
+        assert_eq!(symmetry.apply(&canonical).zobrist(), board.zobrist());
+        for (other, other_turn) in [
+            (board.mirrored(), Color::Black),
+            (board.color_swapped(), Color::White),
+            (board.mirrored().color_swapped(), Color::White),
+        ] {
+            assert_eq!(
+                other.canonical(&other_turn).0.zobrist(),
+                canonical.zobrist()
+            );
+        }
+    }
+}
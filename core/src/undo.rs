@@ -0,0 +1,149 @@
+use crate::{Board, Color, Height, Move, Position};
+
+/*
+ * Applying moves in place.
+ *
+ * Board::apply_move clones the board, which adds up in a search. Board::make_move changes the board
+ * in place and returns an Undo, and passing that Undo to Board::unmake_move puts the board back
+ * exactly how it was. Moves have to be unmade in the reverse order they were made.
+ */
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Undo {
+    mov: Move,
+    // The index, square and height of the piece that was moved, boomed or scored
+    piece: Option<(usize, Position, Height)>,
+    white_score: u8,
+    black_score: u8,
+    victory_by_concession: Option<Color>,
+    plies_played: u16,
+    plies_since_progress: u16,
+    zobrist: u64,
+}
+impl Undo {
+    pub fn mov(&self) -> &Move {
+        &self.mov
+    }
+}
+
+impl Board {
+    pub fn make_move(&mut self, mov: &Move) -> Undo {
+        let piece = match mov {
+            Move::Boom(index) | Move::Zoom(index, _) | Move::Score(index) => {
+                let piece = &self.pieces[*index];
+                Some((*index, piece.position, piece.height))
+            }
+            Move::Concede(_) => None,
+        };
+        let undo = Undo {
+            mov: *mov,
+            piece,
+            white_score: self.white_score,
+            black_score: self.black_score,
+            victory_by_concession: self.victory_by_concession,
+            plies_played: self.plies_played,
+            plies_since_progress: self.plies_since_progress,
+            zobrist: self.zobrist(),
+        };
+        self.zobrist = self.zobrist_after(mov);
+        self.plies_played = self.plies_played.saturating_add(1);
+        self.plies_since_progress = match mov {
+            Move::Boom(_) | Move::Score(_) => 0,
+            _ => self.plies_since_progress.saturating_add(1),
+        };
+        match mov {
+            Move::Boom(index) => {
+                self.pieces[*index].boom();
+            }
+            Move::Zoom(index, position) => {
+                self.pieces[*index].position = *position;
+            }
+            Move::Score(index) => {
+                let points_scored = u8::from(&self.pieces[*index].height);
+                match self.pieces[*index].color {
+                    Color::White => self.white_score += points_scored,
+                    Color::Black => self.black_score += points_scored,
+                }
+                self.pieces[*index].height = Height::Dead;
+            }
+            Move::Concede(color) => {
+                self.victory_by_concession = Some(color.invert());
+            }
+        }
+        undo
+    }
+
+    pub fn unmake_move(&mut self, undo: Undo) {
+        if let Some((index, position, height)) = undo.piece {
+            self.pieces[index].position = position;
+            self.pieces[index].height = height;
+        }
+        self.white_score = undo.white_score;
+        self.black_score = undo.black_score;
+        self.victory_by_concession = undo.victory_by_concession;
+        self.plies_played = undo.plies_played;
+        self.plies_since_progress = undo.plies_since_progress;
+        self.zobrist = undo.zobrist;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    fn assert_same(a: &Board, b: &Board, turn: &Color) {
+        assert_eq!(a.pack(turn), b.pack(turn));
+        assert_eq!(a.zobrist(), b.zobrist());
+    }
+
+    #[test]
+    fn make_and_unmake_random_games() {
+        let mut rng = thread_rng();
+        for _ in 0..50 {
+            let mut board = Board::default();
+            let mut turn = Color::White;
+            let mut history = vec![];
+            while board.winner().is_none() {
+                let mov = if rng.gen_ratio(1, 200) {
+                    Move::Concede(turn)
+                } else {
+                    board.legal_moves(&turn).choose(&mut rng).unwrap()
+                };
+                let expected = board.apply_move(&mov);
+                let before = board.clone();
+                let undo = board.make_move(&mov);
+                assert_eq!(undo.mov(), &mov);
+                history.push((before, undo));
+                turn = turn.invert();
+                assert_same(&board, &expected, &turn);
+            }
+            // Unwind the whole game back to the start
+            while let Some((before, undo)) = history.pop() {
+                board.unmake_move(undo);
+                turn = turn.invert();
+                assert_same(&board, &before, &turn);
+            }
+            assert_same(&board, &Board::default(), &Color::White);
+        }
+    }
+
+    #[test]
+    fn unmake_restores_scores_and_concessions() {
+        let (mut board, _) = Board::from_fen("8/8/8/8/8/8/8/w37 b 2 1 -").unwrap();
+        let before = board.clone();
+        let undo = board.make_move(&Move::Concede(Color::Black));
+        assert_eq!(board.victory_by_concession, Some(Color::White));
+        board.unmake_move(undo);
+        assert_eq!(board.victory_by_concession, None);
+        assert_same(&board, &before, &Color::Black);
+
+        let (mut board, _) = Board::from_fen("w37/8/8/8/8/8/8/8 w 2 1 -").unwrap();
+        let before = board.clone();
+        let undo = board.make_move(&Move::Score(0));
+        assert_eq!(board.white_score, 5);
+        assert_eq!(board.pieces[0].height, Height::Dead);
+        board.unmake_move(undo);
+        assert_same(&board, &before, &Color::White);
+    }
+}
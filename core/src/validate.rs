@@ -0,0 +1,129 @@
+use crate::{Board, Color, Height, LegalMoveIterator, Move};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MoveError {
+    GameOver,
+    // The move is for the other side's piece, or booms one of your own pieces
+    WrongSide,
+    DeadPiece(usize),
+    IllegalDestination(Move),
+    IndexOutOfRange(usize),
+}
+impl std::fmt::Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveError::GameOver => write!(f, "The game is already over"),
+            MoveError::WrongSide => write!(f, "That piece belongs to the wrong side"),
+            MoveError::DeadPiece(index) => write!(f, "Piece {index} is dead"),
+            MoveError::IllegalDestination(mov) => write!(f, "{mov:?} is not a legal move"),
+            MoveError::IndexOutOfRange(index) => write!(f, "There is no piece {index}"),
+        }
+    }
+}
+impl std::error::Error for MoveError {}
+
+impl Board {
+    // Checks that color is allowed to make mov before applying it
+    pub fn try_apply_move(&self, mov: &Move, color: &Color) -> Result<Board, MoveError> {
+        self.validate_move(mov, color)?;
+        Ok(self.apply_move(mov))
+    }
+
+    pub fn validate_move(&self, mov: &Move, color: &Color) -> Result<(), MoveError> {
+        if self.winner().is_some() {
+            return Err(MoveError::GameOver);
+        }
+        let (index, owner) = match mov {
+            Move::Concede(conceder) => {
+                return if conceder == color {
+                    Ok(())
+                } else {
+                    Err(MoveError::WrongSide)
+                };
+            }
+            Move::Boom(index) => (*index, color.invert()),
+            Move::Zoom(index, _) | Move::Score(index) => (*index, *color),
+        };
+        if index >= self.rules.piece_count() {
+            return Err(MoveError::IndexOutOfRange(index));
+        }
+        let piece = &self.pieces[index];
+        if piece.color != owner {
+            return Err(MoveError::WrongSide);
+        }
+        if piece.height == Height::Dead {
+            return Err(MoveError::DeadPiece(index));
+        }
+        let legal = match mov {
+            // Any of our pieces could be doing the booming
+            Move::Boom(_) => self.legal_moves(color).any(|m| &m == mov),
+            _ => LegalMoveIterator::for_piece(self, index).any(|m| &m == mov),
+        };
+        if legal {
+            Ok(())
+        } else {
+            Err(MoveError::IllegalDestination(*mov))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Position;
+
+    fn square(s: &str) -> Position {
+        s.try_into().unwrap()
+    }
+
+    #[test]
+    fn rejects_bad_moves() {
+        let mut board = Board::default();
+        board.pieces[4].position = square("c4");
+        board.pieces[7].height = Height::Dead;
+        let white = Color::White;
+        assert!(board
+            .try_apply_move(&Move::Zoom(0, square("c2")), &white)
+            .is_ok());
+        assert!(board.try_apply_move(&Move::Boom(4), &white).is_ok());
+        assert_eq!(
+            board.validate_move(&Move::Zoom(4, square("c5")), &white),
+            Err(MoveError::WrongSide)
+        );
+        assert_eq!(
+            board.validate_move(&Move::Boom(1), &white),
+            Err(MoveError::WrongSide)
+        );
+        assert_eq!(
+            board.validate_move(&Move::Concede(Color::Black), &white),
+            Err(MoveError::WrongSide)
+        );
+        assert_eq!(
+            board.validate_move(&Move::Boom(7), &white),
+            Err(MoveError::DeadPiece(7))
+        );
+        assert_eq!(
+            board.validate_move(&Move::Zoom(0, square("c5")), &white),
+            Err(MoveError::IllegalDestination(Move::Zoom(0, square("c5"))))
+        );
+        assert_eq!(
+            board.validate_move(&Move::Score(0), &white),
+            Err(MoveError::IllegalDestination(Move::Score(0)))
+        );
+        assert_eq!(
+            board.validate_move(&Move::Boom(5), &white),
+            Err(MoveError::IllegalDestination(Move::Boom(5)))
+        );
+        assert_eq!(
+            board.validate_move(&Move::Zoom(8, square("c2")), &white),
+            Err(MoveError::IndexOutOfRange(8))
+        );
+        let conceded = board
+            .try_apply_move(&Move::Concede(Color::White), &white)
+            .unwrap();
+        assert_eq!(
+            conceded.validate_move(&Move::Zoom(4, square("c5")), &Color::Black),
+            Err(MoveError::GameOver)
+        );
+    }
+}
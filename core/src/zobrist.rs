@@ -0,0 +1,207 @@
+use crate::{Board, Color, Height, Move, Position};
+
+/*
+ * Zobrist keys for board positions.
+ *
+ * Every living piece contributes a key for its color, height and square, so pieces are
+ * interchangeable no matter which index slot they occupy. Each possible score, the side to move and
+ * a concession also have keys. The key is kept up to date incrementally by Board::apply_move.
+ *
+ * Board doesn't know whose turn it is, so the side to move is toggled on every move. A board's key
+ * assumes White is to move when the board is created, unless it was created with Board::from_fen.
+ * After modifying pieces directly, call Board::rehash to bring the key up to date.
+ */
+
+struct ZobristTable {
+    pieces: [[[u64; 64]; 3]; 2],
+    white_score: [u64; 256],
+    black_score: [u64; 256],
+    black_to_move: u64,
+    concession: [u64; 2],
+}
+
+// splitmix64, which is good enough to generate the table at compile time
+const fn next_key(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+const fn generate_table() -> ZobristTable {
+    let mut state = 0x626f6f6d7a6f6f6d;
+    let mut table = ZobristTable {
+        pieces: [[[0; 64]; 3]; 2],
+        white_score: [0; 256],
+        black_score: [0; 256],
+        black_to_move: 0,
+        concession: [0; 2],
+    };
+    let mut color = 0;
+    while color < 2 {
+        let mut height = 0;
+        while height < 3 {
+            let mut square = 0;
+            while square < 64 {
+                table.pieces[color][height][square] = next_key(&mut state);
+                square += 1;
+            }
+            height += 1;
+        }
+        color += 1;
+    }
+    let mut score = 0;
+    while score < 256 {
+        table.white_score[score] = next_key(&mut state);
+        table.black_score[score] = next_key(&mut state);
+        score += 1;
+    }
+    table.black_to_move = next_key(&mut state);
+    table.concession[0] = next_key(&mut state);
+    table.concession[1] = next_key(&mut state);
+    table
+}
+
+static TABLE: ZobristTable = generate_table();
+
+fn color_index(color: &Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+fn piece_key(color: &Color, height: &Height, position: &Position) -> u64 {
+    match height {
+        Height::Dead => 0,
+        _ => {
+            TABLE.pieces[color_index(color)][u8::from(height) as usize - 1]
+                [u8::from(*position) as usize]
+        }
+    }
+}
+
+fn score_key(color: &Color, score: u8) -> u64 {
+    match color {
+        Color::White => TABLE.white_score[score as usize],
+        Color::Black => TABLE.black_score[score as usize],
+    }
+}
+
+impl Board {
+    pub fn zobrist(&self) -> u64 {
+        self.zobrist
+    }
+
+    // Computes the key from scratch rather than incrementally
+    pub fn compute_zobrist(&self, turn: &Color) -> u64 {
+        let mut key = self
+            .pieces
+            .iter()
+            .map(|p| piece_key(&p.color, &p.height, &p.position))
+            .fold(0, |key, piece| key ^ piece);
+        key ^= score_key(&Color::White, self.white_score);
+        key ^= score_key(&Color::Black, self.black_score);
+        if turn == &Color::Black {
+            key ^= TABLE.black_to_move;
+        }
+        if let Some(color) = &self.victory_by_concession {
+            key ^= TABLE.concession[color_index(color)];
+        }
+        key
+    }
+
+    pub fn rehash(&mut self, turn: &Color) {
+        self.zobrist = self.compute_zobrist(turn);
+    }
+
+    // Rehashes a board that was transformed from original, keeping original's side to move, and
+    // switching it if switch_turn is set
+    pub(crate) fn rehash_from(&mut self, original: &Board, switch_turn: bool) {
+        let turn = original.zobrist ^ original.compute_zobrist(&Color::White);
+        self.zobrist = self.compute_zobrist(&Color::White) ^ turn;
+        if switch_turn {
+            self.zobrist ^= TABLE.black_to_move;
+        }
+    }
+
+    // The key of the board after mov is applied. self must not have mov applied yet.
+    pub(crate) fn zobrist_after(&self, mov: &Move) -> u64 {
+        let mut key = self.zobrist ^ TABLE.black_to_move;
+        match mov {
+            Move::Boom(index) => {
+                let piece = &self.pieces[*index];
+                key ^= piece_key(&piece.color, &piece.height, &piece.position);
+                key ^= piece_key(&piece.color, &piece.height.boom(), &piece.position);
+            }
+            Move::Zoom(index, position) => {
+                let piece = &self.pieces[*index];
+                key ^= piece_key(&piece.color, &piece.height, &piece.position);
+                key ^= piece_key(&piece.color, &piece.height, position);
+            }
+            Move::Score(index) => {
+                let piece = &self.pieces[*index];
+                key ^= piece_key(&piece.color, &piece.height, &piece.position);
+                let score = match piece.color {
+                    Color::White => self.white_score,
+                    Color::Black => self.black_score,
+                };
+                key ^= score_key(&piece.color, score);
+                key ^= score_key(&piece.color, score + u8::from(&piece.height));
+            }
+            Move::Concede(color) => {
+                key ^= TABLE.concession[color_index(&color.invert())];
+            }
+        }
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn incremental_matches_from_scratch() {
+        let mut rng = thread_rng();
+        for _ in 0..200 {
+            let mut board = Board::default();
+            let mut turn = Color::White;
+            while board.winner().is_none() {
+                assert_eq!(board.zobrist(), board.compute_zobrist(&turn));
+                let Some(mov) = board.legal_moves(&turn).choose(&mut rng) else {
+                    break;
+                };
+                board = board.apply_move(&mov);
+                turn = turn.invert();
+            }
+            assert_eq!(board.zobrist(), board.compute_zobrist(&turn));
+        }
+    }
+
+    #[test]
+    fn transpositions_share_a_key() {
+        let board = Board::default();
+        let a = board
+            .apply_move(&Move::Zoom(0, "c2".try_into().unwrap()))
+            .apply_move(&Move::Zoom(4, "c7".try_into().unwrap()))
+            .apply_move(&Move::Zoom(1, "d2".try_into().unwrap()));
+        let b = board
+            .apply_move(&Move::Zoom(1, "d2".try_into().unwrap()))
+            .apply_move(&Move::Zoom(4, "c7".try_into().unwrap()))
+            .apply_move(&Move::Zoom(0, "c2".try_into().unwrap()));
+        assert_eq!(a.zobrist(), b.zobrist());
+        // Same pieces, other side to move
+        assert_ne!(board.zobrist(), board.compute_zobrist(&Color::Black));
+    }
+
+    #[test]
+    fn fen_sets_the_side_to_move() {
+        let (board, turn) = Board::from_fen("2b3b3b3b32/8/8/8/8/8/8/2w3w3w3w32 b 0 0 -").unwrap();
+        assert_eq!(turn, Color::Black);
+        assert_eq!(board.zobrist(), board.compute_zobrist(&Color::Black));
+        assert_ne!(board.zobrist(), Board::default().zobrist());
+    }
+}
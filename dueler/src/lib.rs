@@ -1,4 +1,26 @@
-use baz_core::{Board, Color, GamePlayer, Move};
+use std::time::Duration;
+
+use baz_core::{Board, Color, GamePlayer, Move, Position};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ProtocolError {
+    BadMove(String),
+    BadColor(String),
+}
+impl std::fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProtocolError::BadMove(line) => write!(f, "Unable to deserialize \"{line}\""),
+            ProtocolError::BadColor(line) => write!(f, "Unrecognized color statement \"{line}\""),
+        }
+    }
+}
+impl std::error::Error for ProtocolError {}
+impl From<ProtocolError> for std::io::Error {
+    fn from(err: ProtocolError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+    }
+}
 
 pub fn serialize_move(mov: &Move) -> String {
     match mov {
@@ -8,28 +30,30 @@ pub fn serialize_move(mov: &Move) -> String {
         Move::Concede(color) => format!("Concede {color:?}"),
     }
 }
-pub fn deserialize_move(line: &str) -> Move {
+// Only checks that the line is a move, not that the move is legal
+pub fn deserialize_move(line: &str) -> Result<Move, ProtocolError> {
+    let bad_move = || ProtocolError::BadMove(line.trim_end().to_string());
+    let index = |s: &str| s.parse::<usize>().map_err(|_| bad_move());
     if let Some(remainder) = line.strip_prefix("Boom ") {
-        Move::Boom(remainder.trim_end().parse().expect("Invalid index"))
+        Ok(Move::Boom(index(remainder.trim_end())?))
     } else if let Some(remainder) = line.strip_prefix("Zoom ") {
-        let (index, pos) = remainder
-            .trim_end()
-            .split_once(' ')
-            .expect("Malformed zoom serialization");
-        Move::Zoom(
-            index.parse().expect("Invalid index"),
-            pos.parse::<i8>().expect("Invalid position").into(),
-        )
+        let (piece, pos) = remainder.trim_end().split_once(' ').ok_or_else(bad_move)?;
+        let pos = pos
+            .parse::<i8>()
+            .ok()
+            .filter(|pos| (0..64).contains(pos))
+            .ok_or_else(bad_move)?;
+        Ok(Move::Zoom(index(piece)?, Position::from(pos)))
     } else if let Some(remainder) = line.strip_prefix("Score ") {
-        Move::Score(remainder.trim_end().parse().expect("Invalid index"))
+        Ok(Move::Score(index(remainder.trim_end())?))
     } else if let Some(remainder) = line.strip_prefix("Concede ") {
         match remainder.trim_end() {
-            "White" => Move::Concede(Color::White),
-            "Black" => Move::Concede(Color::Black),
-            _ => panic!("Unknown color {remainder}"),
+            "White" => Ok(Move::Concede(Color::White)),
+            "Black" => Ok(Move::Concede(Color::Black)),
+            _ => Err(bad_move()),
         }
     } else {
-        panic!("Unable to deserialize \"{line}\"")
+        Err(bad_move())
     }
 }
 
@@ -38,6 +62,7 @@ where
     T: GamePlayer,
 {
     player: T,
+    time_budget: Option<Duration>,
 }
 
 impl<T> StdioGamePlayer<T>
@@ -45,7 +70,15 @@ where
     T: GamePlayer,
 {
     pub fn new(player: T) -> StdioGamePlayer<T> {
-        StdioGamePlayer { player }
+        StdioGamePlayer {
+            player,
+            time_budget: None,
+        }
+    }
+    // Passed on to the player at the start of the game
+    pub fn with_time_budget(mut self, budget: Duration) -> StdioGamePlayer<T> {
+        self.time_budget = Some(budget);
+        self
     }
     pub fn main(&mut self) -> std::io::Result<()> {
         let stdin = std::io::stdin();
@@ -53,29 +86,79 @@ where
         let mut board = Board::default();
         // Get the color from stdin
         stdin.read_line(&mut buffer)?;
-        let color: Color;
+        let color = match buffer.as_str() {
+            "white\n" => Color::White,
+            "black\n" => Color::Black,
+            _ => return Err(ProtocolError::BadColor(buffer.trim_end().to_string()).into()),
+        };
+        self.player.new_game(&board, &color);
+        if let Some(budget) = self.time_budget {
+            self.player.set_time_budget(budget);
+        }
         // We must make the first move if we are playing white
-        if buffer == "white\n" {
-            color = Color::White;
+        if color == Color::White {
             let our_move = self.player.decide(&board, &color);
             board = board.apply_move(&our_move);
             println!("{}", serialize_move(&our_move));
-        } else if buffer == "black\n" {
-            color = Color::Black;
-        } else {
-            panic!("Unrecognized color statement: {buffer}")
         }
         while board.winner().is_none() {
             // Get the opponents move from stdin and apply it to the board
             buffer = String::new();
             stdin.read_line(&mut buffer)?;
-            let their_move = deserialize_move(&buffer);
-            board = board.apply_move(&their_move);
+            let their_move = deserialize_move(&buffer)?;
+            board = board
+                .try_apply_move(&their_move, &color.invert())
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+            self.player.opponent_moved(&their_move, &board);
+            if board.winner().is_some() {
+                break;
+            }
             // Decide on a move and print it
             let our_move = self.player.decide(&board, &color);
             board = board.apply_move(&our_move);
             println!("{}", serialize_move(&our_move));
         }
+        if let Some(winner) = board.winner() {
+            self.player.game_over(&winner);
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_round_trip() {
+        let moves = [
+            Move::Boom(3),
+            Move::Zoom(5, Position::from(42i8)),
+            Move::Score(0),
+            Move::Concede(Color::Black),
+        ];
+        for mov in moves {
+            let line = format!("{}\n", serialize_move(&mov));
+            assert_eq!(deserialize_move(&line), Ok(mov));
+        }
+    }
+
+    #[test]
+    fn bad_moves() {
+        for line in [
+            "",
+            "Boom\n",
+            "Boom -1\n",
+            "Zoom 5\n",
+            "Zoom 5 64\n",
+            "Score x\n",
+            "Concede Red\n",
+            "Jump 1\n",
+        ] {
+            assert_eq!(
+                deserialize_move(line),
+                Err(ProtocolError::BadMove(line.trim_end().to_string()))
+            );
+        }
+    }
+}
@@ -6,7 +6,7 @@ use std::path::{Path, PathBuf};
 use std::process::{ChildStdin, ChildStdout, Stdio};
 
 use ascii_table::AsciiTable;
-use baz_core::{Board, Color, Winner};
+use baz_core::{Board, Color, DrawReason, GameRecord, Move, RuleSet, Winner};
 use baz_dueler::deserialize_move;
 use clap::{Parser, Subcommand};
 use serde::{Deserialize, Serialize};
@@ -146,10 +146,22 @@ fn play_match(
     config: &Config,
     white_player_name: &str,
     black_player_name: &str,
+    record_dir: Option<&Path>,
+    rules: &RuleSet,
 ) -> MatchResult {
     let mut result = MatchResult::default();
-    for _ in 0..games {
-        match play_game(config, white_player_name, black_player_name) {
+    for game in 0..games {
+        let record = play_game(config, white_player_name, black_player_name, rules);
+        if let Some(record_dir) = record_dir {
+            let path = record_dir.join(format!(
+                "{white_player_name}-vs-{black_player_name}-{}.baz",
+                game + 1
+            ));
+            if let Err(err) = record.save(&path) {
+                eprintln!("Failed to save game record: {err}");
+            }
+        }
+        match record.result.expect("the game must be finished") {
             Winner::White => result.white += 1,
             Winner::Black => result.black += 1,
             Winner::Draw => result.draw += 1,
@@ -158,7 +170,12 @@ fn play_match(
     result
 }
 
-fn play_game(config: &Config, white_player_name: &str, black_player_name: &str) -> Winner {
+fn play_game(
+    config: &Config,
+    white_player_name: &str,
+    black_player_name: &str,
+    rules: &RuleSet,
+) -> GameRecord {
     println!("{white_player_name} vs. {black_player_name}");
     let white_player = config.player(white_player_name);
     let black_player = config.player(black_player_name);
@@ -186,39 +203,89 @@ fn play_game(config: &Config, white_player_name: &str, black_player_name: &str)
     black_stdin
         .write_all("black\n".as_bytes())
         .expect("Failed to write to black process");
-    let mut board = Board::default();
+    let mut board = Board::new(*rules);
     let mut current_color = Color::White;
-    // TODO max turn cutoff results in draw
+    let mut moves = vec![];
+    // The keys of every earlier position, to check for repetition
+    let mut keys = vec![];
+    let mut draw_reason = None;
     while board.winner().is_none() {
+        if board.is_repetition(keys.iter().rev().copied()) {
+            draw_reason = Some(DrawReason::Repetition);
+            break;
+        }
+        keys.push(board.zobrist());
         // println!("{board:?}");
         // std::thread::sleep(Duration::from_millis(1));
-        board = match current_color {
-            Color::White => play_turn(&board, &mut white_stdout, &mut black_stdin),
-            Color::Black => play_turn(&board, &mut black_stdout, &mut white_stdin),
+        let (mov, new_board) = match current_color {
+            Color::White => play_turn(&board, &current_color, &mut white_stdout, &mut black_stdin),
+            Color::Black => play_turn(&board, &current_color, &mut black_stdout, &mut white_stdin),
         };
         // std::thread::sleep(Duration::from_millis(1));
+        moves.push(mov);
+        board = new_board;
         current_color = current_color.invert();
     }
     white_process.kill().expect("Failed to kill white process");
     black_process.kill().expect("Failed to kill black process");
-    board.winner().unwrap()
+    white_process
+        .wait()
+        .expect("Failed to wait for white process");
+    black_process
+        .wait()
+        .expect("Failed to wait for black process");
+    let draw_reason = draw_reason.or(board.draw_reason());
+    if let Some(reason) = draw_reason {
+        println!("Draw: {reason}");
+    }
+    GameRecord {
+        white: Some(white_player_name.to_string()),
+        black: Some(black_player_name.to_string()),
+        result: draw_reason.map_or(board.winner(), |_| Some(Winner::Draw)),
+        extra_tags: draw_reason
+            .map(|reason| ("Termination".to_string(), reason.to_string()))
+            .into_iter()
+            .collect(),
+        moves,
+        ..Default::default()
+    }
 }
 
-fn play_turn(board: &Board, stdout: &mut ChildStdout, stdin: &mut ChildStdin) -> Board {
+fn play_turn(
+    board: &Board,
+    color: &Color,
+    stdout: &mut ChildStdout,
+    stdin: &mut ChildStdin,
+) -> (Move, Board) {
     let mut buffer = String::new();
     let mut reader = BufReader::new(stdout);
-    reader
+    let played = reader
         .read_line(&mut buffer)
-        .expect("Failed to read move from player");
-    let mov = deserialize_move(&buffer);
-    // TODO verify move is legal
-    let new_board = board.apply_move(&mov);
+        .map_err(|err| format!("Failed to read {color:?}'s move: {err}"))
+        .and_then(|_| {
+            deserialize_move(&buffer).map_err(|err| format!("{color:?} sent a bad move: {err}"))
+        })
+        .and_then(|mov| {
+            board
+                .try_apply_move(&mov, color)
+                .map(|new_board| (mov, new_board))
+                .map_err(|err| format!("{color:?} played {mov:?}: {err}"))
+        });
+    let (mov, new_board) = match played {
+        Ok(played) => played,
+        Err(reason) => {
+            // Illegal and unreadable moves forfeit the game
+            println!("{reason}");
+            let concession = Move::Concede(*color);
+            (concession, board.apply_move(&concession))
+        }
+    };
     if new_board.winner().is_none() {
         stdin
             .write_all(buffer.as_bytes())
             .expect("Failed to send move to player");
     }
-    new_board
+    (mov, new_board)
 }
 
 fn print_results(tournament: &[String], results: &HashMap<(&str, &str), MatchResult>) {
@@ -258,6 +325,11 @@ enum Commands {
         update: bool,
         #[arg(long)]
         skip_self: bool,
+        #[arg(long)]
+        record: Option<PathBuf>,
+        // Games that go on for this many plies are drawn
+        #[arg(long)]
+        max_plies: Option<u16>,
         games: usize,
     },
 }
@@ -277,20 +349,34 @@ fn main() {
         Commands::Play {
             update,
             skip_self,
+            record,
+            max_plies,
             games,
         } => {
+            let mut rules = RuleSet::STANDARD;
+            rules.draws.max_plies = max_plies;
             if update {
                 for player_name in config.tournament.iter() {
                     update_artifact(config.player(player_name));
                 }
             }
+            if let Some(record_dir) = &record {
+                std::fs::create_dir_all(record_dir).expect("Failed to create record dir");
+            }
             let mut results: HashMap<(&str, &str), MatchResult> = HashMap::new();
             for white_player_name in config.tournament.iter() {
                 for black_player_name in config.tournament.iter() {
                     if !(skip_self && white_player_name == black_player_name) {
                         results.insert(
                             (white_player_name, black_player_name),
-                            play_match(games, &config, white_player_name, black_player_name),
+                            play_match(
+                                games,
+                                &config,
+                                white_player_name,
+                                black_player_name,
+                                record.as_deref(),
+                                &rules,
+                            ),
                         );
                     }
                 }
@@ -20,6 +20,12 @@ func _ready():
 		pieces.add_child(piece)
 	update_pieces()
 
+# The AI decides in the background, so check every frame whether it has moved
+func _process(_delta):
+	if gameboard.poll_ai():
+		update_pieces()
+		update_decorations()
+
 func update_pieces():
 	var children = pieces.get_children()
 	for i in range(0, 8):
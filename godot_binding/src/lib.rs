@@ -1,6 +1,9 @@
+use std::task::Poll;
 use std::time::Duration;
 
-use baz_core::{Board, Game, GamePlayer, Move};
+use baz_core::{
+    AsyncGamePlayer, Board, Game, GamePlayer, LegalMoveIterator, Move, ThreadedPlayer, Winner,
+};
 use baz_players::{GeniusHeuristic, HResult, MinMaxPlayer};
 use godot::engine::{Node, NodeVirtual};
 use godot::prelude::*;
@@ -17,10 +20,8 @@ struct GodotGameBoard {
     #[base]
     _base: Base<Node>,
 
-    // tx: Sender<Move>,
-    // sync_barrier: Arc<Barrier>,
-    // game: Game<GodotGamePlayer, MinMaxPlayer<GoFasterHeuristic, i8>>,
-    game: Game<GodotGamePlayer, MinMaxPlayer<GeniusHeuristic, HResult<Rational32>>>,
+    // The AI thinks on its own thread, and Board.gd polls it every frame
+    game: Game<GodotGamePlayer, ThreadedPlayer>,
 }
 
 #[godot_api]
@@ -31,10 +32,9 @@ impl NodeVirtual for GodotGameBoard {
         //     GodotGamePlayer {},
         //     MinMaxPlayer::new(GoFasterHeuristic(), depth),
         // );
-        let game = Game::new(
-            GodotGamePlayer {},
-            MinMaxPlayer::new(GeniusHeuristic(), Duration::from_secs(5)),
-        );
+        let ai: MinMaxPlayer<GeniusHeuristic, HResult<Rational32>> =
+            MinMaxPlayer::new(GeniusHeuristic(), Duration::from_secs(5));
+        let game = Game::new(GodotGamePlayer { chosen: None }, ThreadedPlayer::new(ai));
         Self { _base: base, game }
     }
 }
@@ -135,49 +135,101 @@ impl GodotGameBoard {
         // } else {
         //     self.tx.send(Move::Zoom(index as usize, position)).unwrap()
         // }
-        let piece = self.game.board().get_piece(index as usize);
-        if &piece.color != self.game.turn() {
-            return;
-        }
+        let index = index as usize;
         let mov = if let Some(boomed_piece) = self.game.board().get_piece_at(&position) {
             Move::Boom(boomed_piece)
         } else {
-            Move::Zoom(index as usize, position)
+            Move::Zoom(index, position)
         };
-        if self.game.board().legal_moves_for(piece).any(|m| m == mov) {
-            self.game.apply_move(&mov);
-            self.game.play_turn();
+        // Booms don't record the booming piece, so make sure it's the one that was selected
+        if matches!(mov, Move::Boom(_))
+            && (index >= self.game.board().rules.piece_count()
+                || !LegalMoveIterator::for_piece(self.game.board(), index).any(|m| m == mov))
+        {
+            return;
         }
+        self.play_human_move(mov);
     }
 
     #[func]
     fn score(&mut self, index: i64) {
-        let mov = Move::Score(index as usize);
+        self.play_human_move(Move::Score(index as usize));
+    }
+
+    // Hands the move Board.gd picked to the human player and lets the game ask for it, if it's legal
+    fn play_human_move(&mut self, mov: Move) {
         if self
             .game
             .board()
-            .legal_moves_for(self.game.board().get_piece(index as usize))
-            .any(|m| m == mov)
+            .validate_move(&mov, self.game.turn())
+            .is_err()
         {
+            return;
+        }
+        self.game.white_player_mut().chosen = Some(mov);
+        self.game.play_turn();
+        self.start_ai();
+    }
+
+    // Plays the AI's move if it has decided, returning whether it did
+    #[func]
+    fn poll_ai(&mut self) -> bool {
+        if let Poll::Ready(mov) = self.game.black_player_mut().poll() {
             self.game.apply_move(&mov);
-            self.game.play_turn();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn start_ai(&mut self) {
+        if self.game.winner().is_none() && self.game.turn() == &baz_core::Color::Black {
+            let board = self.game.board().clone();
+            self.game
+                .black_player_mut()
+                .start(&board, &baz_core::Color::Black);
         }
     }
+
+    #[func]
+    fn set_ai_time(&mut self, millis: i64) {
+        let budget = Duration::from_millis(millis.max(0) as u64);
+        self.game.set_time_budget(&baz_core::Color::Black, budget);
+    }
+
+    #[func]
+    fn undo(&mut self) {
+        self.game.black_player_mut().cancel();
+        // Take back the AI's reply as well, so that it is the player's turn again
+        if self.game.undo().is_some() && self.game.turn() != &baz_core::Color::White {
+            self.game.undo();
+        }
+    }
+
+    #[func]
+    fn redo(&mut self) {
+        self.game.black_player_mut().cancel();
+        if self.game.redo().is_some() && self.game.turn() != &baz_core::Color::White {
+            self.game.redo();
+        }
+        // The AI's reply may not have been played yet
+        self.start_ai();
+    }
 }
 
+// The human player, whose moves are picked in Board.gd
 struct GodotGamePlayer {
-    // rx: Receiver<Move>,
+    // Set just before the game asks for White's move
+    chosen: Option<Move>,
 }
 
-// impl GodotGamePlayer {
-//     fn new(rx: Receiver<Move>) -> GodotGamePlayer {
-//         GodotGamePlayer { rx }
-//     }
-// }
-
 impl GamePlayer for GodotGamePlayer {
-    fn decide(&mut self, _board: &Board, _color: &baz_core::Color) -> Move {
-        panic!("Not allowed")
-        // self.rx.recv().unwrap()
+    fn decide(&mut self, _board: &Board, color: &baz_core::Color) -> Move {
+        // The game only asks after Board.gd has picked a move. If it somehow asks without one,
+        // conceding is the only move that's always legal.
+        self.chosen.take().unwrap_or(Move::Concede(*color))
+    }
+    fn game_over(&mut self, winner: &Winner) {
+        godot_print!("Game over: {winner:?}");
     }
 }
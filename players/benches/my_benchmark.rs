@@ -1,29 +1,33 @@
-use baz_core::{Board, Color};
-use baz_players::{GeniusHeuristic, GoFastHeuristic, GoFasterHeuristic, Heuristic, NaiveHeuristic};
-use criterion::{criterion_group, criterion_main, Criterion};
+use std::time::Duration;
+
+use baz_core::{Board, Color, MoveList};
+use baz_players::{
+    GeniusHeuristic, GoFastHeuristic, GoFasterHeuristic, Heuristic, MinMaxPlayer, NaiveHeuristic,
+};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
 
 pub fn criterion_benchmark(c: &mut Criterion) {
     let board = Board::default();
     // TODO shuffle the board
-    let mut go_fast = GoFastHeuristic();
+    let go_fast = GoFastHeuristic();
     c.bench_function("go fast heuristic", |b| {
         b.iter(|| {
             go_fast.evaluate(&board, &Color::White);
         })
     });
-    let mut go_faster = GoFasterHeuristic();
+    let go_faster = GoFasterHeuristic();
     c.bench_function("go faster heuristic", |b| {
         b.iter(|| {
             go_faster.evaluate(&board, &Color::White);
         })
     });
-    let mut genius = GeniusHeuristic();
+    let genius = GeniusHeuristic();
     c.bench_function("genius heuristic", |b| {
         b.iter(|| {
             genius.evaluate(&board, &Color::White);
         })
     });
-    let mut naive = NaiveHeuristic();
+    let naive = NaiveHeuristic();
     c.bench_function("naive heuristic", |b| {
         b.iter(|| {
             naive.evaluate(&board, &Color::White);
@@ -31,5 +35,48 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, criterion_benchmark);
+pub fn move_generation_benchmark(c: &mut Criterion) {
+    let board = Board::default();
+    c.bench_function("legal move iterator", |b| {
+        b.iter(|| board.legal_moves(&Color::White).count())
+    });
+    let mut moves = Vec::with_capacity(64);
+    c.bench_function("bitboard move generator", |b| {
+        b.iter(|| {
+            moves.clear();
+            board.generate_moves(&Color::White, &mut moves);
+        })
+    });
+    let mut move_list = MoveList::new();
+    c.bench_function("bitboard move generator into a move list", |b| {
+        b.iter(|| {
+            move_list.clear();
+            board.generate_moves(&Color::White, &mut move_list);
+        })
+    });
+}
+
+// Run this once as is and once with --features clone_search, and criterion will report how making
+// moves in place compares to copying the board
+pub fn search_benchmark(c: &mut Criterion) {
+    let board = Board::default();
+    let mut group = c.benchmark_group("search");
+    group.sample_size(10);
+    // A fresh player every time, so the transposition table doesn't remember the last search
+    group.bench_function("minmax depth 4", |b| {
+        b.iter_batched(
+            || MinMaxPlayer::new(GeniusHeuristic(), Duration::ZERO),
+            |mut player| player.search(&board, &Color::White, 4),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    criterion_benchmark,
+    move_generation_benchmark,
+    search_benchmark
+);
 criterion_main!(benches);
@@ -2,7 +2,7 @@ use baz_core::{Board, Color, Height, Winner};
 use num::{rational::Rational32, ToPrimitive};
 
 use crate::heuristic::{HResult, Heuristic};
-/**
+/*
  * Let's put down some thoughts about how this genius heuristic will work.
  *
  * The fundamental concept here is point density. A piece is worth 1-3 points and will take 1-8
@@ -236,10 +236,7 @@ impl Heuristic<HResult<Rational32>> for GeniusHeuristic {
 
 impl GeniusHeuristic {
     fn estimate_score_and_turns(board: &Board, color: &Color) -> (Rational32, Rational32) {
-        let range = match color {
-            Color::White => 0..4,
-            Color::Black => 4..8,
-        };
+        let range = board.rules.pieces(color);
         // height of the piece, distance to the score zone, if the piece can be boomed
         let piece_data: Vec<(i8, i8, bool)> = range
             .clone()
@@ -248,11 +245,7 @@ impl GeniusHeuristic {
             .map(|(i, p)| {
                 (
                     Into::<i8>::into(&p.height),
-                    if color == &Color::White {
-                        8 - p.position.y()
-                    } else {
-                        1 + p.position.y()
-                    },
+                    board.rules.distance_to_score(color, p.position.y()),
                     board.boomable(i),
                 )
             })
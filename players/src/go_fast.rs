@@ -14,11 +14,7 @@ impl Heuristic<i8> for GoFastHeuristic {
             .map(|p| {
                 (
                     Into::<i8>::into(&p.height),
-                    if color == &Color::White {
-                        7 - p.position.y()
-                    } else {
-                        p.position.y()
-                    },
+                    board.rules.distance_to_score(color, p.position.y()) - 1,
                 )
             })
             .map(|(height, distance)| (distance + height - 1) / height)
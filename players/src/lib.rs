@@ -2,14 +2,22 @@ mod forward_random;
 mod genius;
 mod go_fast;
 mod heuristic;
+mod mcts;
 mod minmax;
 mod naive;
 mod random;
+mod tablebase;
+mod transposition;
 
 pub use forward_random::ForwardRandomPlayer;
 pub use genius::GeniusHeuristic;
 pub use go_fast::{GoFastHeuristic, GoFasterHeuristic};
 pub use heuristic::{HResult, Heuristic, HeuristicPlayer};
+pub use mcts::{
+    Budget, ForwardRandomPlayout, HeuristicPlayout, MctsPlayer, PlayoutPolicy, RandomPlayout,
+};
 pub use minmax::MinMaxPlayer;
 pub use naive::NaiveHeuristic;
 pub use random::RandomPlayer;
+pub use tablebase::{Outcome, Tablebase, TablebaseError, TablebaseHeuristic};
+pub use transposition::{Bound, Entry, TranspositionTable};
@@ -0,0 +1,441 @@
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+
+use baz_core::*;
+use rand::prelude::*;
+use rayon::prelude::*;
+
+use crate::{ForwardRandomPlayer, Heuristic};
+
+/*
+ * Monte Carlo tree search, using UCT to pick which moves to look at.
+ *
+ * Every iteration walks down the tree, picking the child with the best upper confidence bound at
+ * each step, adds one new node, plays the game out from there with a PlayoutPolicy, and adds the
+ * result to every node on the way back up. The move that was visited the most is played. There is
+ * no heuristic involved unless the playout policy uses one.
+ *
+ * Each rayon thread grows its own tree from the root, and their root visits are added up at the
+ * end. The trees are kept between moves: if the new position is two plies below the old root, that
+ * part of each tree is kept and searched further.
+ */
+
+// Playouts that go on for longer than this are called a draw
+const MAX_PLAYOUT_PLIES: usize = 400;
+
+// How often the heuristic playout plays a random move instead of the best one
+const HEURISTIC_EXPLORATION: f64 = 0.1;
+
+pub trait PlayoutPolicy {
+    fn choose(&self, board: &Board, color: &Color) -> Move;
+}
+impl<P: PlayoutPolicy + ?Sized> PlayoutPolicy for Box<P> {
+    fn choose(&self, board: &Board, color: &Color) -> Move {
+        (**self).choose(board, color)
+    }
+}
+
+// Every legal move is equally likely
+pub struct RandomPlayout();
+impl PlayoutPolicy for RandomPlayout {
+    fn choose(&self, board: &Board, color: &Color) -> Move {
+        let moves = board.legal_move_list(color);
+        *moves
+            .choose(&mut rand::thread_rng())
+            .unwrap_or(&Move::Concede(*color))
+    }
+}
+
+// Plays like ForwardRandomPlayer, which gets games over much faster
+pub struct ForwardRandomPlayout();
+impl PlayoutPolicy for ForwardRandomPlayout {
+    fn choose(&self, board: &Board, color: &Color) -> Move {
+        ForwardRandomPlayer().decide(board, color)
+    }
+}
+
+// Mostly plays the move the heuristic likes best, with the occasional random move
+pub struct HeuristicPlayout<H, T>
+where
+    H: Heuristic<T>,
+    T: Clone + Ord,
+{
+    heuristic: H,
+    phantom: PhantomData<T>,
+}
+impl<H, T> HeuristicPlayout<H, T>
+where
+    H: Heuristic<T>,
+    T: Clone + Ord,
+{
+    pub fn new(heuristic: H) -> HeuristicPlayout<H, T> {
+        HeuristicPlayout {
+            heuristic,
+            phantom: PhantomData,
+        }
+    }
+}
+impl<H, T> PlayoutPolicy for HeuristicPlayout<H, T>
+where
+    H: Heuristic<T>,
+    T: Clone + Ord,
+{
+    fn choose(&self, board: &Board, color: &Color) -> Move {
+        let mut rng = rand::thread_rng();
+        let moves = board.legal_move_list(color);
+        if rng.gen_bool(HEURISTIC_EXPLORATION) {
+            return *moves.choose(&mut rng).unwrap_or(&Move::Concede(*color));
+        }
+        let mut board = board.clone();
+        moves
+            .iter()
+            .max_by_key(|m| {
+                let undo = board.make_move(m);
+                let score = self.heuristic.evaluate(&board, color);
+                board.unmake_move(undo);
+                score
+            })
+            .copied()
+            .unwrap_or(Move::Concede(*color))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Budget {
+    Time(Duration),
+    // The total over all the trees
+    Iterations(usize),
+}
+
+struct Node {
+    // The move that led here, which is None for the root
+    mov: Option<Move>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<Move>,
+    // The side to move here
+    turn: Color,
+    key: u64,
+    visits: u32,
+    // From the point of view of whoever made mov: 1 for each win and 0.5 for each draw
+    reward: f64,
+}
+impl Node {
+    fn new(board: &Board, turn: Color, mov: Option<Move>, parent: Option<usize>) -> Node {
+        let untried = if board.winner().is_some() {
+            vec![]
+        } else {
+            board.legal_move_list(&turn).to_vec()
+        };
+        Node {
+            mov,
+            parent,
+            children: vec![],
+            untried,
+            turn,
+            key: board.zobrist(),
+            visits: 0,
+            reward: 0.0,
+        }
+    }
+}
+
+// The root is always node 0
+struct Tree {
+    nodes: Vec<Node>,
+    board: Board,
+}
+impl Tree {
+    fn new(board: &Board, turn: &Color) -> Tree {
+        Tree {
+            nodes: vec![Node::new(board, *turn, None, None)],
+            board: board.clone(),
+        }
+    }
+
+    // The part of the tree below the node for board, if it's two plies down
+    fn reroot(mut self, board: &Board, turn: &Color) -> Option<Tree> {
+        let new_root = self.nodes[0]
+            .children
+            .iter()
+            .flat_map(|&child| self.nodes[child].children.iter())
+            .copied()
+            .find(|&index| {
+                self.nodes[index].key == board.zobrist() && &self.nodes[index].turn == turn
+            })?;
+        // Copy the subtree over breadth first, renumbering it as it goes
+        let mut nodes: Vec<Node> = Vec::new();
+        let mut queue = std::collections::VecDeque::from([(new_root, None)]);
+        while let Some((old_index, parent)) = queue.pop_front() {
+            let new_index = nodes.len();
+            let old = &mut self.nodes[old_index];
+            let node = Node {
+                mov: parent.and(old.mov),
+                parent,
+                children: vec![],
+                untried: std::mem::take(&mut old.untried),
+                turn: old.turn,
+                key: old.key,
+                visits: old.visits,
+                reward: old.reward,
+            };
+            if let Some(parent) = parent {
+                nodes[parent].children.push(new_index);
+            }
+            queue.extend(old.children.iter().map(|&child| (child, Some(new_index))));
+            nodes.push(node);
+        }
+        Some(Tree {
+            nodes,
+            board: board.clone(),
+        })
+    }
+
+    fn select_child(&self, index: usize, exploration: f64) -> usize {
+        let parent_visits = (self.nodes[index].visits as f64).ln();
+        let uct = |child: &usize| {
+            let node = &self.nodes[*child];
+            let visits = node.visits as f64;
+            node.reward / visits + exploration * (parent_visits / visits).sqrt()
+        };
+        *self.nodes[index]
+            .children
+            .iter()
+            .max_by(|a, b| uct(a).total_cmp(&uct(b)))
+            .expect("only nodes with children are selected from")
+    }
+
+    fn iterate(&mut self, policy: &impl PlayoutPolicy, exploration: f64, rng: &mut ThreadRng) {
+        let mut board = self.board.clone();
+        let mut index = 0;
+        // Select
+        while self.nodes[index].untried.is_empty() && !self.nodes[index].children.is_empty() {
+            index = self.select_child(index, exploration);
+            board.make_move(&self.nodes[index].mov.unwrap());
+        }
+        // Expand
+        let node = &mut self.nodes[index];
+        if !node.untried.is_empty() {
+            let mov = node
+                .untried
+                .swap_remove(rng.gen_range(0..node.untried.len()));
+            let turn = node.turn.invert();
+            board.make_move(&mov);
+            let child = Node::new(&board, turn, Some(mov), Some(index));
+            self.nodes.push(child);
+            let child_index = self.nodes.len() - 1;
+            self.nodes[index].children.push(child_index);
+            index = child_index;
+        }
+        // Simulate
+        let winner = playout(policy, &mut board, self.nodes[index].turn);
+        // Backpropagate
+        let mut current = Some(index);
+        while let Some(index) = current {
+            let node = &mut self.nodes[index];
+            node.visits += 1;
+            let mover = node.turn.invert();
+            node.reward += match winner.as_ref().and_then(|w| w.color()) {
+                Some(color) if color == mover => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+            current = node.parent;
+        }
+    }
+
+    fn root_visits(&self) -> impl Iterator<Item = (Move, u32)> + '_ {
+        self.nodes[0]
+            .children
+            .iter()
+            .map(|&child| (self.nodes[child].mov.unwrap(), self.nodes[child].visits))
+    }
+}
+
+// Plays the game out, returning the winner. None means the playout went on too long.
+fn playout(policy: &impl PlayoutPolicy, board: &mut Board, mut turn: Color) -> Option<Winner> {
+    for _ in 0..MAX_PLAYOUT_PLIES {
+        if let Some(winner) = board.winner() {
+            return Some(winner);
+        }
+        let mov = policy.choose(board, &turn);
+        board.make_move(&mov);
+        turn = turn.invert();
+    }
+    board.winner()
+}
+
+pub struct MctsPlayer<P>
+where
+    P: PlayoutPolicy + Sync,
+{
+    policy: P,
+    budget: Budget,
+    exploration: f64,
+    // One tree for each thread
+    trees: Vec<Tree>,
+}
+impl<P> MctsPlayer<P>
+where
+    P: PlayoutPolicy + Sync,
+{
+    pub fn new(policy: P, budget: Budget) -> MctsPlayer<P> {
+        MctsPlayer {
+            policy,
+            budget,
+            exploration: std::f64::consts::SQRT_2,
+            trees: vec![],
+        }
+    }
+
+    // Higher values look at less promising moves more often
+    pub fn with_exploration(mut self, exploration: f64) -> MctsPlayer<P> {
+        self.exploration = exploration;
+        self
+    }
+
+    // The visits to each root move, added up over every tree
+    fn root_visits(&self) -> Vec<(Move, u32)> {
+        let mut visits: Vec<(Move, u32)> = vec![];
+        for (mov, count) in self.trees.iter().flat_map(|tree| tree.root_visits()) {
+            match visits.iter_mut().find(|(m, _)| *m == mov) {
+                Some((_, total)) => *total += count,
+                None => visits.push((mov, count)),
+            }
+        }
+        visits
+    }
+}
+
+impl<P> GamePlayer for MctsPlayer<P>
+where
+    P: PlayoutPolicy + Sync,
+{
+    fn decide(&mut self, board: &Board, color: &Color) -> Move {
+        let start = Instant::now();
+        let threads = rayon::current_num_threads();
+        let trees = std::mem::take(&mut self.trees);
+        self.trees = trees
+            .into_iter()
+            .filter_map(|tree| tree.reroot(board, color))
+            .collect();
+        let reused = self.trees.len();
+        self.trees.truncate(threads);
+        while self.trees.len() < threads {
+            self.trees.push(Tree::new(board, color));
+        }
+        let (policy, exploration, budget) = (&self.policy, self.exploration, self.budget);
+        self.trees
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(thread, tree)| {
+                let mut rng = rand::thread_rng();
+                match budget {
+                    Budget::Time(time) => {
+                        while start.elapsed() < time {
+                            tree.iterate(policy, exploration, &mut rng);
+                        }
+                    }
+                    Budget::Iterations(iterations) => {
+                        // Share the iterations out as evenly as possible
+                        let share =
+                            iterations / threads + usize::from(thread < iterations % threads);
+                        for _ in 0..share {
+                            tree.iterate(policy, exploration, &mut rng);
+                        }
+                    }
+                }
+            });
+        let visits = self.root_visits();
+        let best = visits.iter().max_by_key(|(_, count)| *count);
+        eprintln!(
+            "Decided after {:?} and {} playouts ({reused} trees reused): {best:?}",
+            start.elapsed(),
+            visits.iter().map(|(_, count)| count).sum::<u32>(),
+        );
+        best.map(|(mov, _)| *mov)
+            .or_else(|| board.legal_moves(color).next())
+            .unwrap_or(Move::Concede(*color))
+    }
+    fn new_game(&mut self, _board: &Board, _color: &Color) {
+        self.trees.clear();
+    }
+    fn set_time_budget(&mut self, budget: Duration) {
+        self.budget = Budget::Time(budget);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GoFasterHeuristic;
+
+    #[test]
+    fn finds_the_winning_score() {
+        // White wins by scoring the last piece on the board
+        let (board, turn) = Board::from_fen("w37/8/8/8/8/8/8/b17 w 0 0 -").unwrap();
+        let mut player = MctsPlayer::new(RandomPlayout(), Budget::Iterations(500));
+        assert_eq!(player.decide(&board, &turn), Move::Score(0));
+    }
+
+    #[test]
+    fn spends_the_iteration_budget() {
+        let board = Board::default();
+        for policy in [
+            Box::new(RandomPlayout()) as Box<dyn PlayoutPolicy + Sync>,
+            Box::new(ForwardRandomPlayout()),
+            Box::new(HeuristicPlayout::new(GoFasterHeuristic())),
+        ] {
+            let mut player = MctsPlayer::new(policy, Budget::Iterations(100));
+            let mov = player.decide(&board, &Color::White);
+            assert!(board.legal_moves(&Color::White).any(|m| m == mov));
+            let total: u32 = player.root_visits().iter().map(|(_, count)| count).sum();
+            assert_eq!(total, 100);
+        }
+    }
+
+    #[test]
+    fn reuses_the_tree() {
+        let board = Board::default();
+        let mut player = MctsPlayer::new(ForwardRandomPlayout(), Budget::Iterations(2000));
+        let ours = player.decide(&board, &Color::White);
+        let after_ours = board.apply_move(&ours);
+        // Reply with the move the tree knows the most about
+        let tree = &player.trees[0];
+        let child = tree.nodes[0]
+            .children
+            .iter()
+            .find(|&&c| tree.nodes[c].mov == Some(ours))
+            .unwrap();
+        let theirs = tree.nodes[*child]
+            .children
+            .iter()
+            .max_by_key(|&&c| tree.nodes[c].visits)
+            .map(|&c| tree.nodes[c].mov.unwrap())
+            .unwrap();
+        let expected_visits = tree.nodes[*child]
+            .children
+            .iter()
+            .find(|&&c| tree.nodes[c].mov == Some(theirs))
+            .map(|&c| tree.nodes[c].visits)
+            .unwrap();
+        let after_theirs = after_ours.apply_move(&theirs);
+        let tree = player
+            .trees
+            .remove(0)
+            .reroot(&after_theirs, &Color::White)
+            .unwrap();
+        assert_eq!(tree.nodes[0].visits, expected_visits);
+        assert_eq!(tree.nodes[0].mov, None);
+        assert_eq!(tree.nodes[0].parent, None);
+        for (index, node) in tree.nodes.iter().enumerate().skip(1) {
+            let parent = &tree.nodes[node.parent.unwrap()];
+            assert!(parent.children.contains(&index));
+        }
+        // A position that isn't in the tree starts over
+        assert!(Tree::new(&board, &Color::White)
+            .reroot(&after_theirs, &Color::White)
+            .is_none());
+    }
+}
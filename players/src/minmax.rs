@@ -1,8 +1,12 @@
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use crate::heuristic::Heuristic;
+use crate::tablebase::{Outcome, Tablebase};
+use crate::transposition::{Bound, TranspositionTable};
 use baz_core::*;
 use rand::seq::SliceRandom;
 use rayon::prelude::*;
@@ -17,8 +21,32 @@ where
     max_width: usize,
     time_per_turn: Duration,
     timeout: Option<Instant>,
+    // Whether the last pass got as far as max_depth anywhere. If it didn't, every line ended in a
+    // finished game and searching deeper won't change anything.
+    reached_max_depth: AtomicBool,
+    // Kept between searches, so each deepening pass can build on the last
+    table: TranspositionTable<T>,
+    tablebase: Option<Arc<Tablebase>>,
     phantom: PhantomData<T>,
 }
+
+const DEFAULT_TABLE_MEMORY: usize = 16 << 20;
+// Every boom lowers a piece, so boom chains end on their own, but they can get very bushy
+const MAX_QUIESCENCE_DEPTH: usize = 6;
+
+// Calls search on the board after mov, and leaves the board as it was. The search makes and unmakes
+// moves in place, unless the clone_search feature is on, which copies the board for every move the
+// way the search used to. That's only there so the benchmarks can compare the two.
+fn after_move<R>(board: &mut Board, mov: &Move, search: impl FnOnce(&mut Board) -> R) -> R {
+    if cfg!(feature = "clone_search") {
+        return search(&mut board.apply_move(mov));
+    }
+    let undo = board.make_move(mov);
+    let result = search(board);
+    board.unmake_move(undo);
+    result
+}
+
 impl<H, T> MinMaxPlayer<H, T>
 where
     H: Heuristic<T> + Sync,
@@ -28,13 +56,54 @@ where
         MinMaxPlayer {
             heuristic,
             max_depth: 0,
-            max_width: 0,
+            max_width: 6, // TODO tune this
             time_per_turn,
             timeout: None,
+            reached_max_depth: AtomicBool::new(false),
+            table: TranspositionTable::with_memory(DEFAULT_TABLE_MEMORY),
+            tablebase: None,
             phantom: PhantomData,
         }
     }
-    fn minimax0(&self, board: &Board, color: &Color) -> (T, Move) {
+    // Resizes the transposition table to fit in roughly bytes, which forgets everything in it
+    pub fn with_table_memory(mut self, bytes: usize) -> MinMaxPlayer<H, T> {
+        self.table = TranspositionTable::with_memory(bytes);
+        self
+    }
+    // Endgames in the tablebase are scored exactly instead of searched
+    pub fn with_tablebase(mut self, tablebase: Arc<Tablebase>) -> MinMaxPlayer<H, T> {
+        self.tablebase = Some(tablebase);
+        self
+    }
+    // Scores are from color's point of view, so the table can't share them between colors
+    fn table_key(board: &Board, color: &Color) -> u64 {
+        match color {
+            Color::White => board.zobrist(),
+            Color::Black => !board.zobrist(),
+        }
+    }
+    fn timed_out(&self) -> bool {
+        self.timeout.is_some_and(|timeout| Instant::now() > timeout)
+    }
+    // Moves the table's best move to the front, if there is one
+    fn order_best_first<X>(items: &mut [X], best_move: Option<Move>, mov: impl Fn(&X) -> Move) {
+        if let Some(index) = best_move.and_then(|b| items.iter().position(|x| mov(x) == b)) {
+            items[..=index].rotate_right(1);
+        }
+    }
+    // Searches to a fixed depth with no time limit
+    pub fn search(&mut self, board: &Board, color: &Color, depth: usize) -> (T, Move) {
+        self.timeout = None;
+        self.max_depth = depth;
+        let (score, pv) = self
+            .minimax0(board, color, &[])
+            .expect("searches without a timeout can't be aborted");
+        (score, pv.first().copied().unwrap_or(Move::Concede(*color)))
+    }
+    // Returns the score and the principal variation, or None if the search ran out of time. pv is
+    // the principal variation from the last search, and those moves are searched first.
+    fn minimax0(&self, board: &Board, color: &Color, pv: &[Move]) -> Option<(T, Vec<Move>)> {
+        // Each root move gets its own copy of the board so they can be searched in parallel
         let mut scores_and_boards = board
             .legal_moves(color)
             .map(|m| (m, board.apply_move(&m)))
@@ -43,84 +112,254 @@ where
         let mut rng = rand::thread_rng();
         scores_and_boards.shuffle(&mut rng);
         scores_and_boards.sort_by(|(h1, _, _), (h2, _, _)| h2.cmp(h1));
-        let (best_score, best_move) = scores_and_boards
-            .par_iter()
-            .map(|(_estimate, new_move, new_board)| {
-                let (new_score, _) = self.minimax(new_board, color, false, H::min(), H::max(), 1);
-                (new_score, *new_move)
+        let key = Self::table_key(board, color);
+        let table_move = self.table.probe(key).and_then(|entry| entry.best_move());
+        Self::order_best_first(&mut scores_and_boards, table_move, |(_, m, _)| *m);
+        Self::order_best_first(&mut scores_and_boards, pv.first().copied(), |(_, m, _)| *m);
+        let results = scores_and_boards
+            .into_par_iter()
+            .map(|(_estimate, new_move, mut new_board)| {
+                let child_pv = Self::follow_pv(pv, &new_move);
+                let (new_score, mut new_pv) = self.minimax(
+                    &mut new_board,
+                    color,
+                    false,
+                    H::min(),
+                    H::max(),
+                    1,
+                    child_pv,
+                )?;
+                new_pv.insert(0, new_move);
+                Some((new_score, new_pv))
             })
-            .max_by_key(|(score, _move)| score.clone())
-            .unwrap_or((H::min(), Move::Concede(*color)));
-        (best_score, best_move)
+            .collect::<Option<Vec<(T, Vec<Move>)>>>()?;
+        let (best_score, best_pv) = results
+            .into_iter()
+            .max_by_key(|(score, _pv)| score.clone())
+            .unwrap_or((H::min(), vec![Move::Concede(*color)]));
+        self.table.store(
+            key,
+            self.max_depth,
+            Bound::Exact,
+            best_score.clone(),
+            best_pv.first().copied(),
+        );
+        Some((best_score, best_pv))
+    }
+    // The rest of the principal variation, if mov is the next move in it
+    fn follow_pv<'a>(pv: &'a [Move], mov: &Move) -> &'a [Move] {
+        match pv.split_first() {
+            Some((first, rest)) if first == mov => rest,
+            _ => &[],
+        }
     }
+    // The board is changed in place while searching, but is always put back before returning
+    #[allow(clippy::too_many_arguments)]
     fn minimax(
         &self,
-        board: &Board,
+        board: &mut Board,
         color: &Color,
         maximizing: bool,
         mut alpha: T,
         mut beta: T,
         depth: usize,
-    ) -> (T, Option<Move>) {
+        pv: &[Move],
+    ) -> Option<(T, Vec<Move>)> {
         if let Some(winner) = board.winner() {
-            if let Some(winner_color) = winner.color() {
-                if &winner_color == color {
-                    return (H::max(), None);
-                } else {
-                    return (H::min(), None);
+            let score = match winner.color() {
+                Some(winner_color) if &winner_color == color => H::max(),
+                Some(_) => H::min(),
+                None => H::draw(),
+            };
+            return Some((score, vec![]));
+        }
+        let piece_color = if maximizing { *color } else { color.invert() };
+        let probe = self
+            .tablebase
+            .as_ref()
+            .and_then(|t| t.probe(board, &piece_color));
+        if let Some(outcome) = probe {
+            // The outcome is for piece_color, which is only us if we're maximizing
+            let score = match (outcome, maximizing) {
+                (Outcome::Draw, _) => H::draw(),
+                (Outcome::Win(_), true) | (Outcome::Loss(_), false) => H::max(),
+                (Outcome::Win(_), false) | (Outcome::Loss(_), true) => H::min(),
+            };
+            return Some((score, vec![]));
+        }
+        if depth >= self.max_depth {
+            self.reached_max_depth.store(true, Ordering::Relaxed);
+            let score = self.quiesce(board, color, maximizing, alpha, beta, 0);
+            return Some((score, vec![]));
+        }
+        // Abandon the whole pass, rather than return a score that wasn't searched properly
+        if self.timed_out() {
+            return None;
+        }
+        let key = Self::table_key(board, color);
+        let remaining = self.max_depth - depth;
+        let entry = self.table.probe(key);
+        if let Some(entry) = entry.as_ref().filter(|entry| entry.depth >= remaining) {
+            let usable = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha,
+            };
+            if usable {
+                // The entry came from a search at least this deep, so count it as reaching max_depth
+                self.reached_max_depth.store(true, Ordering::Relaxed);
+                return Some((entry.score.clone(), entry.best_move().into_iter().collect()));
+            }
+        }
+        let (original_alpha, original_beta) = (alpha.clone(), beta.clone());
+        let moves = board.legal_move_list(&piece_color);
+        let mut scores_and_moves = moves
+            .iter()
+            .map(|&m| {
+                let estimate = after_move(board, &m, |board| self.heuristic.evaluate(board, color));
+                (estimate, m)
+            })
+            .collect::<Vec<(T, Move)>>();
+        let mut rng = rand::thread_rng();
+        scores_and_moves.shuffle(&mut rng);
+        scores_and_moves
+            .sort_by(|(h1, _), (h2, _)| if maximizing { h2.cmp(h1) } else { h1.cmp(h2) });
+        // The best move from an earlier search is the most likely to cause a cutoff, and the
+        // principal variation from the last pass is the most likely of all
+        Self::order_best_first(
+            &mut scores_and_moves,
+            entry.and_then(|e| e.best_move()),
+            |(_, m)| *m,
+        );
+        Self::order_best_first(&mut scores_and_moves, pv.first().copied(), |(_, m)| *m);
+        let mut best_score = if maximizing { H::min() } else { H::max() };
+        let mut best_pv = vec![];
+        for (_estimate, new_move) in scores_and_moves.iter().take(self.max_width) {
+            let (new_score, mut new_pv) = after_move(board, new_move, |board| {
+                self.minimax(
+                    board,
+                    color,
+                    !maximizing,
+                    alpha.clone(),
+                    beta.clone(),
+                    depth + 1,
+                    Self::follow_pv(pv, new_move),
+                )
+            })?;
+            let improved = if maximizing {
+                new_score > best_score
+            } else {
+                new_score < best_score
+            };
+            if !improved {
+                continue;
+            }
+            best_score = new_score;
+            new_pv.insert(0, *new_move);
+            best_pv = new_pv;
+            if maximizing {
+                if best_score > beta {
+                    break;
                 }
+                alpha = alpha.max(best_score.clone());
             } else {
-                return (H::draw(), None);
+                if best_score < alpha {
+                    break;
+                }
+                beta = beta.min(best_score.clone());
             }
         }
-        if depth >= self.max_depth {
-            return (self.heuristic.evaluate(board, color), None);
+        let bound = if best_score >= original_beta {
+            Bound::Lower
+        } else if best_score <= original_alpha {
+            Bound::Upper
+        } else {
+            Bound::Exact
+        };
+        self.table.store(
+            key,
+            remaining,
+            bound,
+            best_score.clone(),
+            best_pv.first().copied(),
+        );
+        Some((best_score, best_pv))
+    }
+}
+
+impl<H, T> MinMaxPlayer<H, T>
+where
+    H: Heuristic<T> + Sync,
+    T: Clone + Debug + Ord + Sync + Send,
+{
+    // Past the search horizon, booms and scores are still played out until the position is quiet,
+    // so that a piece left next to an enemy isn't valued as if it were safe. The side to move can
+    // always stand pat and take the heuristic's score instead, if that's better than any boom.
+    fn quiesce(
+        &self,
+        board: &mut Board,
+        color: &Color,
+        maximizing: bool,
+        mut alpha: T,
+        mut beta: T,
+        depth: usize,
+    ) -> T {
+        if let Some(winner) = board.winner() {
+            return match winner.color() {
+                Some(winner_color) if &winner_color == color => H::max(),
+                Some(_) => H::min(),
+                None => H::draw(),
+            };
         }
-        if let Some(timeout) = self.timeout {
-            if Instant::now() > timeout {
-                return (self.heuristic.evaluate(board, color), None);
+        let stand_pat = self.heuristic.evaluate(board, color);
+        if depth >= MAX_QUIESCENCE_DEPTH {
+            return stand_pat;
+        }
+        if maximizing {
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat.clone());
+        } else {
+            if stand_pat <= alpha {
+                return stand_pat;
             }
+            beta = beta.min(stand_pat.clone());
         }
         let piece_color = if maximizing { *color } else { color.invert() };
-        let mut scores_and_boards = board
-            .legal_moves(&piece_color)
-            .map(|m| (m, board.apply_move(&m)))
-            .map(|(m, b)| (self.heuristic.evaluate(&b, color), m, b))
-            .collect::<Vec<(T, Move, Board)>>();
-        let mut rng = rand::thread_rng();
-        scores_and_boards.shuffle(&mut rng);
-        scores_and_boards
-            .sort_by(|(h1, _, _), (h2, _, _)| if maximizing { h2.cmp(h1) } else { h1.cmp(h2) });
-        let mut best_score = if maximizing { H::min() } else { H::max() };
-        let mut best_move = None;
-        for (_estimate, new_move, new_board) in scores_and_boards.iter().take(self.max_width) {
-            let (new_score, _) = self.minimax(
-                new_board,
-                color,
-                !maximizing,
-                alpha.clone(),
-                beta.clone(),
-                depth + 1,
-            );
+        let moves = board.legal_move_list(&piece_color);
+        let mut best_score = stand_pat;
+        for mov in moves
+            .iter()
+            .filter(|m| matches!(m, Move::Boom(_) | Move::Score(_)))
+        {
+            let score = after_move(board, mov, |board| {
+                self.quiesce(
+                    board,
+                    color,
+                    !maximizing,
+                    alpha.clone(),
+                    beta.clone(),
+                    depth + 1,
+                )
+            });
             if maximizing {
-                if new_score > best_score {
-                    best_score = new_score;
-                    best_move = Some(new_move);
-                    if best_score > beta {
+                if score > best_score {
+                    best_score = score;
+                    if best_score >= beta {
                         break;
                     }
                     alpha = alpha.max(best_score.clone());
                 }
-            } else if new_score < best_score {
-                best_score = new_score;
-                best_move = Some(new_move);
-                if best_score < alpha {
+            } else if score < best_score {
+                best_score = score;
+                if best_score <= alpha {
                     break;
                 }
                 beta = beta.min(best_score.clone());
-            };
+            }
         }
-        (best_score, best_move.copied())
+        best_score
     }
 }
 
@@ -130,59 +369,177 @@ where
     T: Clone + Debug + Ord + Sync + Send,
 {
     fn decide(&mut self, board: &Board, color: &Color) -> Move {
-        let now = Instant::now();
-        self.timeout = Some(now + self.time_per_turn);
-        let mut best_move = (H::min(), Move::Concede(*color));
-        let mut last_best_move = (H::min(), Move::Concede(*color));
-        self.max_depth = 3;
-        self.max_width = 6; // TODO tune this
-        while Instant::now() - now < self.time_per_turn {
-            last_best_move = best_move;
-            best_move = self.minimax0(board, color);
-            self.max_depth += 1;
-        }
-        let time_taken = Instant::now() - now;
+        let start = Instant::now();
+        // Every tablebase win looks the same to the search, so let the tablebase pick the fastest
+        let table_move = self
+            .tablebase
+            .as_ref()
+            .and_then(|t| t.best_move(board, color));
+        if let Some(mov) = table_move {
+            eprintln!("Decided from the tablebase: {mov:?}");
+            return mov;
+        }
+        // Depth 1 is always searched in full, so there is a move to play however little time there is
+        self.timeout = None;
+        self.max_depth = 1;
+        let (mut score, mut pv) = self
+            .minimax0(board, color, &[])
+            .expect("searches without a timeout can't be aborted");
+        let mut completed_depth = 1;
+        let deadline = start + self.time_per_turn;
+        self.timeout = Some(deadline);
+        // There's no point looking deeper once the result is certain
+        while score != H::max() && score != H::min() && Instant::now() < deadline {
+            self.max_depth = completed_depth + 1;
+            self.reached_max_depth.store(false, Ordering::Relaxed);
+            match self.minimax0(board, color, &pv) {
+                Some((new_score, new_pv)) => {
+                    (score, pv) = (new_score, new_pv);
+                    completed_depth = self.max_depth;
+                    if !self.reached_max_depth.load(Ordering::Relaxed) {
+                        break;
+                    }
+                }
+                // Out of time, so keep the last pass that finished
+                None => break,
+            }
+        }
         eprintln!(
-            "Decided after {time_taken:?} and a max depth of {}: {:?}",
-            self.max_depth - 2,
-            last_best_move
+            "Decided after {:?} at depth {completed_depth}: {score:?} {pv:?}",
+            start.elapsed()
         );
-        // Intentionally ignore the abortive partially calculated result
-        last_best_move.1
+        pv.first().copied().unwrap_or(Move::Concede(*color))
+    }
+    fn set_time_budget(&mut self, budget: Duration) {
+        self.time_per_turn = budget;
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::GeniusHeuristic;
+    use crate::{GeniusHeuristic, HResult};
 
     use super::*;
 
+    #[test]
+    fn search_fills_the_table() {
+        let board = Board::default();
+        let mut player =
+            MinMaxPlayer::new(GeniusHeuristic(), Duration::ZERO).with_table_memory(1 << 16);
+        let (score, mov) = player.search(&board, &Color::White, 3);
+        let entry = player.table.probe(board.zobrist()).unwrap();
+        assert_eq!(entry.depth, 3);
+        assert_eq!(entry.score, score);
+        assert_eq!(entry.best_move(), Some(mov));
+        assert!(board.legal_moves(&Color::White).any(|m| m == mov));
+        // Black's view of the same position is kept separately
+        assert!(player.table.probe(!board.zobrist()).is_none());
+        player.search(&board, &Color::Black, 2);
+        assert_eq!(player.table.probe(!board.zobrist()).unwrap().depth, 2);
+    }
+
+    #[test]
+    fn decides_without_any_time() {
+        // Depth 1 always finishes, so the move is never a concession
+        let board = Board::default();
+        let mut player = MinMaxPlayer::new(GeniusHeuristic(), Duration::ZERO);
+        let mov = player.decide(&board, &Color::White);
+        assert!(board.legal_moves(&Color::White).any(|m| m == mov));
+        // The deadline has already passed, so no deeper pass is even started
+        assert_eq!(player.max_depth, 1);
+    }
+
+    #[test]
+    fn stops_deepening_when_every_line_is_finished() {
+        // Whatever White does, the no progress rule draws the game on the next ply
+        let (board, _) = Board::from_fen("7b1/8/8/8/8/8/8/w17 w 0 0 - 99 0").unwrap();
+        let mut player = MinMaxPlayer::new(GeniusHeuristic(), Duration::from_millis(50));
+        let start = Instant::now();
+        let mov = player.decide(&board, &Color::White);
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(board.legal_moves(&Color::White).any(|m| m == mov));
+        assert_eq!(player.max_depth, 2);
+    }
+
+    #[test]
+    fn principal_variation_is_played_out() {
+        let board = Board::default();
+        let mut player: MinMaxPlayer<GeniusHeuristic, HResult<num::Rational32>> =
+            MinMaxPlayer::new(GeniusHeuristic(), Duration::ZERO);
+        player.max_depth = 1;
+        let (_, pv) = player.minimax0(&board, &Color::White, &[]).unwrap();
+        assert_eq!(pv.len(), 1);
+        player.max_depth = 3;
+        let (_, pv) = player.minimax0(&board, &Color::White, &pv).unwrap();
+        assert_eq!(pv.len(), 3);
+        // Every move in it is legal in turn
+        let mut board = board;
+        let mut turn = Color::White;
+        for mov in pv.iter() {
+            board = board.try_apply_move(mov, &turn).unwrap();
+            turn = turn.invert();
+        }
+    }
+
+    #[test]
+    fn quiescence_sees_booms_past_the_horizon() {
+        let player: MinMaxPlayer<GeniusHeuristic, HResult<num::Rational32>> =
+            MinMaxPlayer::new(GeniusHeuristic(), Duration::ZERO);
+        let (min, max) = (GeniusHeuristic::min(), GeniusHeuristic::max());
+        // White's one is in reach of Black's three, and it's Black's move
+        let (mut board, _) = Board::from_fen("8/8/3b34/8/3w14/8/8/w1w1w15 b 0 0 -").unwrap();
+        let heuristic = GeniusHeuristic().evaluate(&board, &Color::White);
+        let quiet = player.quiesce(
+            &mut board,
+            &Color::White,
+            false,
+            min.clone(),
+            max.clone(),
+            0,
+        );
+        assert!(quiet < heuristic);
+        // With nothing to boom, it's just the heuristic
+        let (mut board, _) = Board::from_fen("8/3b34/8/8/8/8/3w14/w1w1w15 b 0 0 -").unwrap();
+        let heuristic = GeniusHeuristic().evaluate(&board, &Color::White);
+        let quiet = player.quiesce(&mut board, &Color::White, false, min, max, 0);
+        assert_eq!(quiet, heuristic);
+    }
+
+    #[test]
+    fn tablebase_positions_are_exact() {
+        let tablebase = crate::tablebase::two_piece_tablebase();
+        let mut player: MinMaxPlayer<GeniusHeuristic, HResult<num::Rational32>> =
+            MinMaxPlayer::new(GeniusHeuristic(), Duration::ZERO).with_tablebase(tablebase);
+        // Black's one is too far away to stop White's three from scoring
+        let (board, turn) = Board::from_fen("7b1/8/w37/8/8/8/8/8 b 0 0 -").unwrap();
+        let (score, _) = player.search(&board, &turn, 1);
+        assert_eq!(score, HResult::Loss);
+        // With White to move, the tablebase picks the quickest win
+        let (board, turn) = Board::from_fen("7b1/8/w37/8/8/8/8/8 w 0 0 -").unwrap();
+        assert_eq!(player.decide(&board, &turn), Move::Score(0));
+    }
+
     #[test]
     fn test_final_moves() {
-        let mut board = Board::default();
         /*
-         * 8 w.......
+         * 8 ........
          * 7 ........
          * 6 ........
          * 5 ........
-         * 4 ........
+         * 4 w.......
          * 3 ........
          * 2 ........
-         * 1 b.......
+         * 1 .......b
          *   abcdefgh
          */
-        board.pieces[0].position = "a4".try_into().unwrap();
-        board.pieces[1].height = Height::Dead;
-        board.pieces[2].height = Height::Dead;
-        board.pieces[3].height = Height::Dead;
-        board.pieces[4].position = "h1".try_into().unwrap();
-        board.pieces[5].height = Height::Dead;
-        board.pieces[6].height = Height::Dead;
-        board.pieces[7].height = Height::Dead;
+        let (board, _) = Board::from_fen("8/8/8/8/w37/8/8/7b3 w 0 0 -").unwrap();
 
+        // White's three can't score or reach Black's three in one move, and Black's three scores
+        // on its next move, so White has lost whatever it plays
         let mut player = MinMaxPlayer::new(GeniusHeuristic(), Duration::from_millis(10));
-        assert_eq!(player.decide(&board, &Color::White), Move::Score(0));
-        assert_eq!(1, 2);
+        let (score, _) = player.search(&board, &Color::White, 2);
+        assert_eq!(score, HResult::Loss);
+        let mov = player.decide(&board, &Color::White);
+        assert!(board.legal_moves(&Color::White).any(|m| m == mov));
     }
 }
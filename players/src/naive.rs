@@ -48,21 +48,14 @@ impl Heuristic<HResult<Rational32>> for NaiveHeuristic {
 
 impl NaiveHeuristic {
     fn score_and_turns(board: &Board, color: &Color) -> (i32, i32) {
-        let range = match color {
-            Color::White => 0..4,
-            Color::Black => 4..8,
-        };
+        let range = board.rules.pieces(color);
         board.pieces[range]
             .iter()
             .filter(|p| p.height != Height::Dead)
             .map(|p| {
                 (
                     Into::<i32>::into(Into::<i8>::into(&p.height)),
-                    if color == &Color::White {
-                        8 - p.position.y() as i32
-                    } else {
-                        1 + p.position.y() as i32
-                    },
+                    board.rules.distance_to_score(color, p.position.y()) as i32,
                 )
             })
             .filter(|(height, _distance)| *height > 0)
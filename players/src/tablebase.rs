@@ -0,0 +1,746 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+use crate::heuristic::Heuristic;
+use baz_core::*;
+use rayon::prelude::*;
+
+/*
+ * Endgame tablebases.
+ *
+ * Once only a few pieces are left there are few enough positions to solve every one of them
+ * exactly. Tablebase::generate solves every position on the standard board with up to max_pieces
+ * live pieces, and stores whether the side to move wins, draws or loses, and how many plies it
+ * takes to end the game with best play. The winner ends it as quickly as possible, and the loser
+ * holds out as long as possible.
+ *
+ * Zooms never change how many points are left on the board, and booms and scores always lower it,
+ * so positions are solved in layers, starting with the fewest points left. Inside a layer, results
+ * are spread backwards over zooms from the positions that are already decided, shortest first. A
+ * position that is never decided this way can dodge forever, and is a draw.
+ *
+ * Keys are like Board::compress, with White always the side to move. If Black is to move, the board
+ * is flipped and the colors are swapped first. Each side's live pieces are sorted into its slots,
+ * and whichever of the board and its mirror image has the smaller key is used. Instead of both
+ * scores, only the side to move's lead is kept, which is all that decides the game.
+ *
+ * The draw rules are ignored while solving. A probe only gives a win or a loss if it can be played
+ * out before the draw counters run out.
+ */
+
+const SIDE: usize = RuleSet::STANDARD.pieces_per_side;
+const MAGIC: &[u8; 5] = b"BAZTB";
+const VERSION: u8 = 1;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Outcome {
+    // The side to move wins, lasting this many plies
+    Win(u16),
+    Draw,
+    Loss(u16),
+}
+impl Outcome {
+    pub fn distance(&self) -> Option<u16> {
+        match self {
+            Outcome::Win(distance) | Outcome::Loss(distance) => Some(*distance),
+            Outcome::Draw => None,
+        }
+    }
+    // The outcome for the side that just moved into this one
+    fn before(&self) -> Outcome {
+        match self {
+            Outcome::Win(distance) => Outcome::Loss(distance + 1),
+            Outcome::Draw => Outcome::Draw,
+            Outcome::Loss(distance) => Outcome::Win(distance + 1),
+        }
+    }
+    // Quick wins are best and quick losses are worst
+    fn rank(&self) -> (u8, i32) {
+        match self {
+            Outcome::Win(distance) => (2, -(*distance as i32)),
+            Outcome::Draw => (1, 0),
+            Outcome::Loss(distance) => (0, *distance as i32),
+        }
+    }
+    // The outcome of a finished game, for the side to move
+    fn of_winner(winner: &Winner, turn: &Color) -> Outcome {
+        match winner.color() {
+            Some(color) if &color == turn => Outcome::Win(0),
+            Some(_) => Outcome::Loss(0),
+            None => Outcome::Draw,
+        }
+    }
+}
+impl Ord for Outcome {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+impl PartialOrd for Outcome {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Debug)]
+pub enum TablebaseError {
+    Io(std::io::Error),
+    NotATablebase,
+    UnknownVersion(u8),
+    InvalidEntry,
+    TooManyPieces(usize),
+}
+impl std::fmt::Display for TablebaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TablebaseError::Io(err) => write!(f, "{err}"),
+            TablebaseError::NotATablebase => write!(f, "Not a tablebase"),
+            TablebaseError::UnknownVersion(version) => {
+                write!(f, "Unknown tablebase version {version}")
+            }
+            TablebaseError::InvalidEntry => write!(f, "Invalid tablebase entry"),
+            TablebaseError::TooManyPieces(pieces) => {
+                write!(f, "There are only 8 pieces, not {pieces}")
+            }
+        }
+    }
+}
+impl std::error::Error for TablebaseError {}
+impl From<std::io::Error> for TablebaseError {
+    fn from(err: std::io::Error) -> Self {
+        TablebaseError::Io(err)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+struct Key {
+    pieces: u64,
+    lead: i8,
+}
+impl Key {
+    // Only call this on boards that aren't finished, or the lead might not fit
+    fn new(board: &Board, turn: &Color) -> Key {
+        let flip = turn == &Color::Black;
+        let lead = match turn {
+            Color::White => board.white_score as i16 - board.black_score as i16,
+            Color::Black => board.black_score as i16 - board.white_score as i16,
+        };
+        let encode = |mirror: bool| -> u64 {
+            let mut pieces = 0;
+            for (side, color) in [*turn, turn.invert()].iter().enumerate() {
+                // This is called for every move while generating, so it's worth avoiding a Vec
+                let mut bytes = [0; SIDE];
+                let mut count = 0;
+                for piece in board.pieces[board.rules.pieces(color)].iter() {
+                    if piece.height == Height::Dead {
+                        continue;
+                    }
+                    let (x, y) = (piece.position.x(), piece.position.y());
+                    let x = if mirror { 7 - x } else { x };
+                    let y = if flip { 7 - y } else { y };
+                    bytes[count] =
+                        (u8::from(&piece.height) << 6) + u8::from(Position::from((x, y)));
+                    count += 1;
+                }
+                bytes[..count].sort_unstable_by(|a, b| b.cmp(a));
+                for (slot, byte) in bytes[..count].iter().enumerate() {
+                    pieces |= (*byte as u64) << (8 * (SIDE * side + slot));
+                }
+            }
+            pieces
+        };
+        Key {
+            pieces: encode(false).min(encode(true)),
+            lead: lead as i8,
+        }
+    }
+    // The board this key was made from, or one of its mirror images, with White to move
+    fn board(&self) -> Board {
+        let white_score = self.lead.max(0) as u8;
+        let black_score = (-self.lead).max(0) as u8;
+        Board::decompress(self.pieces, white_score, black_score)
+    }
+    // Whether Key::new could have made this key from an unfinished board with up to max_pieces live
+    // pieces. The board it decodes to has to encode back to the same key, which rules out dead
+    // pieces with positions, pieces out of order and the larger of two mirror images.
+    fn is_valid(&self, max_pieces: usize) -> bool {
+        let board = self.board();
+        let squares: Vec<Position> = board.pieces[..board.rules.piece_count()]
+            .iter()
+            .filter(|piece| piece.height != Height::Dead)
+            .map(|piece| piece.position)
+            .collect();
+        let overlapping = (1..squares.len()).any(|i| squares[..i].contains(&squares[i]));
+        squares.len() <= max_pieces
+            && !overlapping
+            && board.winner().is_none()
+            && Key::new(&board, &Color::White) == *self
+    }
+}
+
+// Every way to pick the heights of count pieces, tallest first
+fn height_sets(count: usize, tallest: u8) -> Vec<Vec<u8>> {
+    if count == 0 {
+        return vec![vec![]];
+    }
+    (1..=tallest)
+        .flat_map(|height| {
+            height_sets(count - 1, height)
+                .into_iter()
+                .map(move |mut rest| {
+                    rest.insert(0, height);
+                    rest
+                })
+        })
+        .collect()
+}
+
+// Puts each piece in pieces on every free square in turn, and calls found with every arrangement.
+// pieces is (slot, height), and pieces of the same side and height only go in increasing squares,
+// since swapping them around gives the same position.
+fn place(
+    pieces: &[(usize, u8)],
+    key: u64,
+    previous: Option<(usize, u8, u8)>,
+    found: &mut impl FnMut(u64),
+) {
+    let Some(((slot, height), rest)) = pieces.split_first() else {
+        found(key);
+        return;
+    };
+    let first_square = match previous {
+        Some((previous_slot, previous_height, square))
+            if previous_height == *height && previous_slot / SIDE == slot / SIDE =>
+        {
+            square + 1
+        }
+        _ => 0,
+    };
+    for square in first_square..64 {
+        let taken = (0..2 * SIDE).any(|other| {
+            let byte = (key >> (8 * other)) & 0xff;
+            byte != 0 && byte as u8 & 0b111111 == square
+        });
+        if !taken {
+            let byte = ((height << 6) + square) as u64;
+            place(
+                rest,
+                key | byte << (8 * slot),
+                Some((*slot, *height, square)),
+                found,
+            );
+        }
+    }
+}
+
+// Every position with at most max_pieces live pieces and exactly points left on the board
+fn layer(max_pieces: usize, points: u8) -> Vec<Key> {
+    // Each way of picking the heights and the lead is placed on its own thread
+    let mut materials = vec![];
+    for white_count in 1..=SIDE {
+        for black_count in 1..=SIDE.min(max_pieces.saturating_sub(white_count)) {
+            for white_heights in height_sets(white_count, 3) {
+                for black_heights in height_sets(black_count, 3) {
+                    let white_points: u8 = white_heights.iter().sum();
+                    let black_points: u8 = black_heights.iter().sum();
+                    if white_points + black_points != points {
+                        continue;
+                    }
+                    let pieces: Vec<(usize, u8)> = white_heights
+                        .iter()
+                        .enumerate()
+                        .chain(black_heights.iter().enumerate().map(|(i, h)| (SIDE + i, h)))
+                        .map(|(slot, height)| (slot, *height))
+                        .collect();
+                    // Any bigger lead and the game is already over
+                    for lead in -(white_points as i8)..=black_points as i8 {
+                        materials.push((pieces.clone(), lead));
+                    }
+                }
+            }
+        }
+    }
+    let mut keys: Vec<Key> = materials
+        .into_par_iter()
+        .flat_map_iter(|(pieces, lead)| {
+            let mut keys = vec![];
+            place(&pieces, 0, None, &mut |pieces| {
+                let board = Key { pieces, lead }.board();
+                keys.push(Key::new(&board, &Color::White));
+            });
+            keys
+        })
+        .collect();
+    // Mirror images and color swaps of the same position come up more than once
+    keys.par_sort_unstable();
+    keys.dedup();
+    keys
+}
+
+fn live_pieces(board: &Board) -> usize {
+    board.pieces[..board.rules.piece_count()]
+        .iter()
+        .filter(|piece| piece.height != Height::Dead)
+        .count()
+}
+
+pub struct Tablebase {
+    max_pieces: usize,
+    table: HashMap<Key, Outcome>,
+}
+
+impl Tablebase {
+    // Anything past 3 pieces takes far too long and far too much memory to be practical. 3 pieces
+    // is about 24 million positions, which takes a few minutes and about 300MB written out.
+    pub fn generate(max_pieces: usize) -> Result<Tablebase, TablebaseError> {
+        if max_pieces > RuleSet::STANDARD.piece_count() {
+            return Err(TablebaseError::TooManyPieces(max_pieces));
+        }
+        let mut tablebase = Tablebase {
+            max_pieces,
+            table: HashMap::new(),
+        };
+        for points in 2..=3 * max_pieces as u8 {
+            tablebase.solve_layer(layer(max_pieces, points));
+        }
+        Ok(tablebase)
+    }
+
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    // The result for the side to move, if the position is in the table and the draw rules can't
+    // get in the way. Finished games aren't in the table.
+    pub fn probe(&self, board: &Board, turn: &Color) -> Option<Outcome> {
+        let outcome = self.lookup(board, turn)?;
+        let Some(distance) = outcome.distance() else {
+            return Some(outcome);
+        };
+        let draws = &board.rules.draws;
+        let fits = |counter: u16, limit: Option<u16>| {
+            limit.is_none_or(|limit| counter as u32 + distance as u32 <= limit as u32)
+        };
+        (fits(board.plies_since_progress, draws.no_progress_plies)
+            && fits(board.plies_played, draws.max_plies))
+        .then_some(outcome)
+    }
+
+    // The move that gets the best result as quickly as possible, or holds out as long as possible
+    pub fn best_move(&self, board: &Board, turn: &Color) -> Option<Move> {
+        self.probe(board, turn)?;
+        board
+            .legal_moves(turn)
+            .filter_map(|mov| {
+                let after = board.apply_move(&mov);
+                let outcome = self.outcome_of(&after, &turn.invert())?;
+                Some((outcome.before(), mov))
+            })
+            .max_by_key(|(outcome, _)| *outcome)
+            .map(|(_, mov)| mov)
+    }
+
+    // The stored result, ignoring the draw counters
+    fn lookup(&self, board: &Board, turn: &Color) -> Option<Outcome> {
+        if !board.rules.has_standard_board()
+            || board.winner().is_some()
+            || live_pieces(board) > self.max_pieces
+        {
+            return None;
+        }
+        self.table.get(&Key::new(board, turn)).copied()
+    }
+
+    fn outcome_of(&self, board: &Board, turn: &Color) -> Option<Outcome> {
+        match board.winner() {
+            Some(winner) => Some(Outcome::of_winner(&winner, turn)),
+            None => self.lookup(board, turn),
+        }
+    }
+
+    fn solve_layer(&mut self, keys: Vec<Key>) {
+        struct Node {
+            // Zooms that haven't been found to lose yet
+            unresolved: usize,
+            // The best result from a boom or a score, which leave the layer
+            exit: Option<Outcome>,
+            outcome: Option<Outcome>,
+        }
+        // Decided positions waiting to be spread backwards, by distance
+        let mut buckets: Vec<Vec<(usize, Outcome)>> = vec![];
+        fn settle(
+            nodes: &mut [Node],
+            buckets: &mut Vec<Vec<(usize, Outcome)>>,
+            i: usize,
+            outcome: Outcome,
+        ) {
+            match outcome.distance() {
+                Some(distance) => {
+                    let distance = distance as usize;
+                    if buckets.len() <= distance {
+                        buckets.resize_with(distance + 1, Vec::new);
+                    }
+                    buckets[distance].push((i, outcome));
+                }
+                None => nodes[i].outcome = Some(outcome),
+            }
+        }
+        // Looking at every move is most of the work, and each position can be done on its own
+        let mut nodes: Vec<Node> = keys
+            .par_iter()
+            .map(|key| {
+                let board = key.board();
+                let mut successors = vec![];
+                let mut exit = None;
+                for mov in board.legal_moves(&Color::White) {
+                    let after = board.apply_move(&mov);
+                    match mov {
+                        Move::Zoom(..) => successors.push(Key::new(&after, &Color::Black)),
+                        _ => {
+                            let outcome = self
+                                .outcome_of(&after, &Color::Black)
+                                .expect("layers with fewer points are solved first")
+                                .before();
+                            exit = exit.max(Some(outcome));
+                        }
+                    }
+                }
+                successors.sort_unstable();
+                successors.dedup();
+                Node {
+                    unresolved: successors.len(),
+                    exit,
+                    outcome: None,
+                }
+            })
+            .collect();
+        for i in 0..nodes.len() {
+            match nodes[i].exit {
+                Some(outcome @ Outcome::Win(_)) => settle(&mut nodes, &mut buckets, i, outcome),
+                // A side with no moves at all can't do anything about it, so call it a draw
+                exit if nodes[i].unresolved == 0 => {
+                    settle(&mut nodes, &mut buckets, i, exit.unwrap_or(Outcome::Draw))
+                }
+                _ => {}
+            }
+        }
+        let mut distance = 0;
+        while distance < buckets.len() {
+            // Nothing is ever added to the bucket being worked on, so it can be done all at once
+            let mut decided = vec![];
+            for (i, outcome) in std::mem::take(&mut buckets[distance]) {
+                if nodes[i].outcome.is_none() {
+                    nodes[i].outcome = Some(outcome);
+                    decided.push((i, outcome));
+                }
+            }
+            let predecessors: Vec<Vec<usize>> = decided
+                .par_iter()
+                .map(|(i, _)| {
+                    // Black zooms from here undo White's zooms into here
+                    let board = keys[*i].board();
+                    let mut predecessors: Vec<usize> = board
+                        .legal_moves(&Color::Black)
+                        .filter(|mov| matches!(mov, Move::Zoom(..)))
+                        .map(|mov| {
+                            let key = Key::new(&board.apply_move(&mov), &Color::Black);
+                            // layer() sorts the keys
+                            keys.binary_search(&key).expect("zooms stay in the layer")
+                        })
+                        .collect();
+                    predecessors.sort_unstable();
+                    predecessors.dedup();
+                    predecessors
+                })
+                .collect();
+            for ((_, outcome), predecessors) in decided.into_iter().zip(predecessors) {
+                for j in predecessors {
+                    if nodes[j].outcome.is_some() {
+                        continue;
+                    }
+                    match outcome {
+                        Outcome::Loss(_) => settle(&mut nodes, &mut buckets, j, outcome.before()),
+                        _ => {
+                            nodes[j].unresolved -= 1;
+                            let winning_exit = matches!(nodes[j].exit, Some(Outcome::Win(_)));
+                            if nodes[j].unresolved == 0 && !winning_exit {
+                                let best = nodes[j].exit.max(Some(outcome.before())).unwrap();
+                                settle(&mut nodes, &mut buckets, j, best);
+                            }
+                        }
+                    }
+                }
+            }
+            distance += 1;
+        }
+        for (key, node) in keys.into_iter().zip(nodes) {
+            self.table
+                .insert(key, node.outcome.unwrap_or(Outcome::Draw));
+        }
+    }
+
+    pub fn write_to(&self, mut writer: impl Write) -> std::io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION, self.max_pieces as u8])?;
+        writer.write_all(&(self.table.len() as u64).to_le_bytes())?;
+        let mut entries: Vec<(&Key, &Outcome)> = self.table.iter().collect();
+        entries.sort_unstable();
+        for (key, outcome) in entries {
+            let (kind, distance) = match outcome {
+                Outcome::Draw => (0, 0),
+                Outcome::Win(distance) => (1, *distance),
+                Outcome::Loss(distance) => (2, *distance),
+            };
+            writer.write_all(&key.pieces.to_le_bytes())?;
+            writer.write_all(&[key.lead as u8, kind])?;
+            writer.write_all(&distance.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from(mut reader: impl Read) -> Result<Tablebase, TablebaseError> {
+        let mut header = [0; 15];
+        reader.read_exact(&mut header)?;
+        if &header[..5] != MAGIC {
+            return Err(TablebaseError::NotATablebase);
+        }
+        if header[5] != VERSION {
+            return Err(TablebaseError::UnknownVersion(header[5]));
+        }
+        let max_pieces = header[6] as usize;
+        if max_pieces > RuleSet::STANDARD.piece_count() {
+            return Err(TablebaseError::TooManyPieces(max_pieces));
+        }
+        let len = u64::from_le_bytes(header[7..].try_into().unwrap());
+        let mut table = HashMap::new();
+        for _ in 0..len {
+            let mut entry = [0; 12];
+            reader.read_exact(&mut entry)?;
+            let key = Key {
+                pieces: u64::from_le_bytes(entry[..8].try_into().unwrap()),
+                lead: entry[8] as i8,
+            };
+            if !key.is_valid(max_pieces) {
+                return Err(TablebaseError::InvalidEntry);
+            }
+            let distance = u16::from_le_bytes([entry[10], entry[11]]);
+            let outcome = match entry[9] {
+                0 => Outcome::Draw,
+                1 => Outcome::Win(distance),
+                2 => Outcome::Loss(distance),
+                _ => return Err(TablebaseError::InvalidEntry),
+            };
+            table.insert(key, outcome);
+        }
+        Ok(Tablebase { max_pieces, table })
+    }
+}
+
+// Another heuristic, except that positions in the tablebase get exact results
+pub struct TablebaseHeuristic<H> {
+    heuristic: H,
+    tablebase: Arc<Tablebase>,
+}
+
+impl<H> TablebaseHeuristic<H> {
+    pub fn new(heuristic: H, tablebase: Arc<Tablebase>) -> TablebaseHeuristic<H> {
+        TablebaseHeuristic {
+            heuristic,
+            tablebase,
+        }
+    }
+}
+
+impl<H, T> Heuristic<T> for TablebaseHeuristic<H>
+where
+    H: Heuristic<T>,
+    T: Clone + Ord,
+{
+    fn evaluate(&self, board: &Board, color: &Color) -> T {
+        // Heuristics aren't told whose move it is, so this is only exact if it doesn't matter
+        let ours = self.tablebase.probe(board, color);
+        let theirs = self.tablebase.probe(board, &color.invert());
+        match (ours, theirs) {
+            (Some(Outcome::Win(_)), Some(Outcome::Loss(_))) => H::max(),
+            (Some(Outcome::Loss(_)), Some(Outcome::Win(_))) => H::min(),
+            (Some(Outcome::Draw), Some(Outcome::Draw)) => H::draw(),
+            _ => self.heuristic.evaluate(board, color),
+        }
+    }
+    fn min() -> T {
+        H::min()
+    }
+    fn max() -> T {
+        H::max()
+    }
+    fn draw() -> T {
+        H::draw()
+    }
+}
+
+// Generating even the smallest tablebase takes a moment, so the tests share one
+#[cfg(test)]
+pub(crate) fn two_piece_tablebase() -> Arc<Tablebase> {
+    static TABLEBASE: std::sync::OnceLock<Arc<Tablebase>> = std::sync::OnceLock::new();
+    TABLEBASE
+        .get_or_init(|| Arc::new(Tablebase::generate(2).unwrap()))
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GoFastHeuristic;
+
+    fn position(fen: &str) -> (Board, Color) {
+        Board::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn solves_two_pieces() {
+        let tablebase = two_piece_tablebase();
+        assert_eq!(tablebase.max_pieces(), 2);
+        assert!(matches!(
+            Tablebase::generate(9),
+            Err(TablebaseError::TooManyPieces(9))
+        ));
+        assert!(!tablebase.is_empty());
+        // Black's one can score from b1, so White has to boom it to get a draw
+        let (board, _) = position("8/8/8/8/8/8/8/w1b16 w 0 0 -");
+        assert_eq!(tablebase.probe(&board, &Color::White), Some(Outcome::Draw));
+        assert_eq!(
+            tablebase.best_move(&board, &Color::White),
+            Some(Move::Boom(4))
+        );
+        assert_eq!(
+            tablebase.probe(&board, &Color::Black),
+            Some(Outcome::Win(1))
+        );
+        assert_eq!(
+            tablebase.best_move(&board, &Color::Black),
+            Some(Move::Score(4))
+        );
+        // Equivalent positions share an entry
+        for turn in [Color::White, Color::Black] {
+            let outcome = tablebase.probe(&board, &turn);
+            assert_eq!(tablebase.probe(&board.mirrored(), &turn), outcome);
+            assert_eq!(
+                tablebase.probe(&board.color_swapped(), &turn.invert()),
+                outcome
+            );
+        }
+        // Too many pieces, or a finished game
+        assert_eq!(tablebase.probe(&Board::default(), &Color::White), None);
+        let (board, _) = position("8/8/8/8/8/8/8/w17 w 0 0 -");
+        assert_eq!(tablebase.probe(&board, &Color::White), None);
+    }
+
+    #[test]
+    fn every_entry_follows_from_its_moves() {
+        let tablebase = two_piece_tablebase();
+        for (key, outcome) in tablebase.table.iter().step_by(37) {
+            let board = key.board();
+            let best = board
+                .legal_moves(&Color::White)
+                .map(|mov| {
+                    let after = board.apply_move(&mov);
+                    tablebase
+                        .outcome_of(&after, &Color::Black)
+                        .unwrap()
+                        .before()
+                })
+                .max()
+                .unwrap();
+            assert_eq!(&best, outcome, "{board:?}");
+        }
+    }
+
+    #[test]
+    fn draw_counters_limit_probes() {
+        let tablebase = two_piece_tablebase();
+        let (key, outcome) = tablebase
+            .table
+            .iter()
+            .max_by_key(|(_, outcome)| outcome.distance())
+            .unwrap();
+        let distance = outcome.distance().unwrap();
+        assert!(distance > 1);
+        let mut board = key.board();
+        let limit = board.rules.draws.no_progress_plies.unwrap();
+        board.plies_since_progress = limit - distance;
+        assert_eq!(tablebase.probe(&board, &Color::White), Some(*outcome));
+        board.plies_since_progress += 1;
+        assert_eq!(tablebase.probe(&board, &Color::White), None);
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let tablebase = two_piece_tablebase();
+        let mut bytes = vec![];
+        tablebase.write_to(&mut bytes).unwrap();
+        let read = Tablebase::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(read.max_pieces(), 2);
+        assert_eq!(read.table, tablebase.table);
+
+        // Files that claim more pieces than there are, or hold keys Key::new can't make
+        let mut too_many = bytes.clone();
+        too_many[6] = 9;
+        assert!(matches!(
+            Tablebase::read_from(too_many.as_slice()),
+            Err(TablebaseError::TooManyPieces(9))
+        ));
+        let mut finished = bytes.clone();
+        finished[15 + 8] = 100;
+        assert!(matches!(
+            Tablebase::read_from(finished.as_slice()),
+            Err(TablebaseError::InvalidEntry)
+        ));
+        let mut dead_with_position = bytes.clone();
+        dead_with_position[15] = 1;
+        assert!(matches!(
+            Tablebase::read_from(dead_with_position.as_slice()),
+            Err(TablebaseError::InvalidEntry)
+        ));
+
+        bytes[0] = b'X';
+        assert!(matches!(
+            Tablebase::read_from(bytes.as_slice()),
+            Err(TablebaseError::NotATablebase)
+        ));
+        assert!(matches!(
+            Tablebase::read_from(&bytes[..3]),
+            Err(TablebaseError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn heuristic_is_exact_when_the_turn_does_not_matter() {
+        let heuristic = TablebaseHeuristic::new(GoFastHeuristic(), two_piece_tablebase());
+        // White's three scores next move, and Black's one is too far away to do anything about it
+        let (board, _) = position("7b1/8/w37/8/8/8/8/8 w 0 0 -");
+        assert_eq!(
+            heuristic.evaluate(&board, &Color::White),
+            GoFastHeuristic::max()
+        );
+        assert_eq!(
+            heuristic.evaluate(&board, &Color::Black),
+            GoFastHeuristic::min()
+        );
+        // Black wins if it moves first, but not if White does, so it's left to the other heuristic
+        let (board, _) = position("8/8/8/8/8/8/8/w1b16 w 0 0 -");
+        assert_eq!(
+            heuristic.evaluate(&board, &Color::White),
+            GoFastHeuristic().evaluate(&board, &Color::White)
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use std::sync::Mutex;
+
+use baz_core::Move;
+
+/*
+ * A fixed size transposition table, so that positions reached more than once aren't searched again.
+ *
+ * Entries are keyed on the board's zobrist key, and each slot holds a single entry. A new entry
+ * replaces whatever is in its slot, unless the slot already holds a deeper search of the same
+ * position. The table doesn't know anything about the draw counters, so positions that only differ
+ * by how close they are to a draw share an entry.
+ *
+ * Every slot has its own lock, so the root moves can be searched in parallel without getting in each
+ * other's way.
+ */
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Bound {
+    // The score is exact
+    Exact,
+    // The search failed high, so the score is at least this
+    Lower,
+    // The search failed low, so the score is at most this
+    Upper,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Entry<T> {
+    key: u64,
+    // How many plies were searched below this position
+    pub depth: usize,
+    pub bound: Bound,
+    pub score: T,
+    // Encoded to keep entries small
+    best_move: Option<u16>,
+}
+impl<T> Entry<T> {
+    pub fn best_move(&self) -> Option<Move> {
+        self.best_move.and_then(|mov| Move::try_from(mov).ok())
+    }
+}
+
+pub struct TranspositionTable<T> {
+    slots: Vec<Mutex<Option<Entry<T>>>>,
+}
+impl<T> TranspositionTable<T>
+where
+    T: Clone,
+{
+    // The biggest power of two number of slots that fits in bytes, but always at least one
+    pub fn with_memory(bytes: usize) -> TranspositionTable<T> {
+        let slot_size = std::mem::size_of::<Mutex<Option<Entry<T>>>>();
+        let mut count = 1;
+        while count * 2 * slot_size <= bytes {
+            count *= 2;
+        }
+        TranspositionTable {
+            slots: (0..count).map(|_| Mutex::new(None)).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    fn slot(&self, key: u64) -> &Mutex<Option<Entry<T>>> {
+        &self.slots[key as usize & (self.slots.len() - 1)]
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry<T>> {
+        let slot = self.slot(key).lock().unwrap();
+        slot.as_ref().filter(|entry| entry.key == key).cloned()
+    }
+
+    pub fn store(&self, key: u64, depth: usize, bound: Bound, score: T, best_move: Option<Move>) {
+        let mut slot = self.slot(key).lock().unwrap();
+        if slot
+            .as_ref()
+            .is_some_and(|entry| entry.key == key && entry.depth > depth)
+        {
+            return;
+        }
+        *slot = Some(Entry {
+            key,
+            depth,
+            bound,
+            score,
+            best_move: best_move.map(u16::from),
+        });
+    }
+
+    pub fn clear(&mut self) {
+        for slot in self.slots.iter_mut() {
+            *slot.get_mut().unwrap() = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_to_the_memory_budget() {
+        let slot_size = std::mem::size_of::<Mutex<Option<Entry<i8>>>>();
+        assert_eq!(TranspositionTable::<i8>::with_memory(0).len(), 1);
+        assert_eq!(
+            TranspositionTable::<i8>::with_memory(slot_size * 5).len(),
+            4
+        );
+        assert_eq!(
+            TranspositionTable::<i8>::with_memory(slot_size * 1024).len(),
+            1024
+        );
+    }
+
+    #[test]
+    fn stores_and_replaces_entries() {
+        let mut table = TranspositionTable::with_memory(1 << 10);
+        let slots = table.len() as u64;
+        assert_eq!(table.probe(7), None);
+        table.store(7, 3, Bound::Exact, 10i8, Some(Move::Boom(5)));
+        let entry = table.probe(7).unwrap();
+        assert_eq!(
+            (entry.depth, entry.bound, entry.score),
+            (3, Bound::Exact, 10)
+        );
+        assert_eq!(entry.best_move(), Some(Move::Boom(5)));
+        // A key that lands in the same slot isn't mistaken for this one
+        assert_eq!(table.probe(7 + slots), None);
+        // A shallower search doesn't replace a deeper one of the same position
+        table.store(7, 2, Bound::Lower, 20, None);
+        assert_eq!(table.probe(7).unwrap().score, 10);
+        table.store(7, 4, Bound::Upper, 30, None);
+        assert_eq!(table.probe(7).unwrap().bound, Bound::Upper);
+        assert_eq!(table.probe(7).unwrap().best_move(), None);
+        // But any other position does
+        table.store(7 + slots, 1, Bound::Exact, 40, None);
+        assert_eq!(table.probe(7), None);
+        assert_eq!(table.probe(7 + slots).unwrap().score, 40);
+        table.clear();
+        assert_eq!(table.probe(7 + slots), None);
+    }
+}